@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use crate::{Result, commands::diff};
+use shika_database::{Diff, Severity};
+use shika_workspace::Workspace;
+
+/// Classify the changes from the old snapshot to the new one under the compatibility policy of
+/// the workspace.
+///
+/// Returns whether the changes pass, i.e. whether none of them is at least as severe as the
+/// policy's `fail_on`.
+pub fn command(old: &Path, new: &Path) -> Result<bool> {
+    let workspace = Workspace::load()?;
+    let policy = &workspace.config.compatibility;
+
+    let diff = Diff::new(&diff::load(old)?, &diff::load(new)?);
+    if diff.is_empty() {
+        println!("No changes.");
+        return Ok(true);
+    }
+
+    let mut severities = Vec::new();
+    for change in &diff.changes {
+        let severity = policy.severity(change);
+        severities.push(severity);
+        println!("{:<9} {change}", severity.to_string());
+    }
+
+    let count = |severity| severities.iter().filter(|s| **s == severity).count();
+    println!();
+    println!(
+        "{} breaking, {} risky and {} safe change(s).",
+        count(Severity::Breaking),
+        count(Severity::Risky),
+        count(Severity::Safe)
+    );
+
+    let failed = severities.iter().filter(|s| policy.fails(**s)).count();
+    if failed > 0 {
+        println!(
+            "{failed} change(s) not allowed by the policy (fail_on: {}).",
+            policy.fail_on
+        );
+    }
+
+    Ok(failed == 0)
+}
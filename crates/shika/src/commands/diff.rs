@@ -0,0 +1,61 @@
+use std::path::{Path, PathBuf};
+
+use crate::{DATABASE_FILE_PATH, Result, commands::pull, error::Error};
+use shika_database::{Database, Diff};
+use shika_workspace::Workspace;
+use tokio::runtime::Runtime;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Compare two snapshots. The old snapshot defaults to the pulled workspace snapshot, and the
+/// new one to the live database.
+pub fn command(
+    old: Option<PathBuf>,
+    new: Option<PathBuf>,
+    database_url: impl FnOnce() -> String,
+    format: Format,
+) -> Result<()> {
+    let workspace = Workspace::load()?;
+
+    let old = match old {
+        Some(path) => load(&path)?,
+        None => workspace
+            .read::<Database, _>(DATABASE_FILE_PATH)?
+            .ok_or(Error::DatabaseNotPulled)?,
+    };
+
+    let new = match new {
+        Some(path) => load(&path)?,
+        None => {
+            let Ok(runtime) = Runtime::new() else {
+                return Err(Error::RuntimeInitializationFailed);
+            };
+
+            runtime.block_on(pull::introspect(&workspace, &database_url()))?
+        }
+    };
+
+    let diff = Diff::new(&old, &new);
+    match format {
+        Format::Text if diff.is_empty() => println!("No changes."),
+        Format::Text => print!("{diff}"),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+    }
+
+    Ok(())
+}
+
+/// Load a snapshot file, brought into canonical order so that only real changes show up.
+pub(crate) fn load(path: &Path) -> Result<Database> {
+    let Some(mut database) = Database::load(path)? else {
+        return Err(Error::SnapshotNotFound(path.display().to_string()));
+    };
+    database.sort();
+
+    Ok(database)
+}
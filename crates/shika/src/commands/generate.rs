@@ -7,42 +7,36 @@ pub fn command(template_name: Option<String>) -> Result<()> {
     // Load the workspace
     let workspace = Workspace::load()?;
     let config = workspace.config.clone();
-    let renderer = Renderer::new(&workspace)?;
+    let mut renderer = Renderer::new(&workspace)?;
 
     let Some(database) = workspace.read::<Database, _>(DATABASE_FILE_PATH)? else {
         return Err(Error::DatabaseNotPulled);
     };
 
-    if let Some(template_name) = template_name {
-        let Some(template) = config.templates.get(&template_name) else {
-            return Err(crate::error::Error::TemplateNotFound(template_name));
-        };
+    let templates = match template_name {
+        Some(template_name) => {
+            let Some(template) = config.templates.get(&template_name) else {
+                return Err(crate::error::Error::TemplateNotFound(template_name));
+            };
 
-        match renderer.render(&template.path, &database.into()) {
-            Ok(output) => {
-                if let Err(error) = workspace.write(&template.output, output) {
-                    eprintln!("Could not write template to file: {error}");
+            vec![template]
+        }
+        None => config.templates.values().collect(),
+    };
+
+    for template in templates {
+        match renderer.render(template, &database) {
+            Ok(outputs) => {
+                for output in outputs {
+                    if let Err(error) = workspace.write_file(&output.path, &output.content) {
+                        eprintln!("Could not write template to {}: {error}", output.path);
+                    }
                 }
             }
             Err(error) => {
                 eprintln!("Could not render template at {}: {}", template.path, error);
             }
         }
-    } else {
-        config.templates.iter().for_each(|(_, template)| {
-            let database = database.clone();
-
-            match renderer.render(&template.path, &database.into()) {
-                Ok(output) => {
-                    if let Err(error) = workspace.write_file(&template.output, &output) {
-                        eprintln!("Could not write template to file: {error}");
-                    }
-                }
-                Err(error) => {
-                    eprintln!("Could not render template at {}: {}", template.path, error);
-                }
-            }
-        });
     }
 
     // Print a success message
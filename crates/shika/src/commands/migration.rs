@@ -0,0 +1,145 @@
+use std::{
+    fs::read_dir,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    Result,
+    commands::{diff, pull},
+    error::Error,
+};
+use shika_database::{Diff, Source};
+use shika_workspace::{MigrationFormat, Workspace};
+use tokio::runtime::Runtime;
+
+/// Write a migration that brings the database to the pulled snapshot of the workspace, which
+/// is treated as the desired state. The current state is the live database, or the `from`
+/// snapshot file.
+///
+/// Returns the written files, which are empty when there is nothing to migrate. Migrations are
+/// PostgreSQL, so a workspace whose source is MySQL or SQLite is rejected.
+pub fn command(
+    name: &str,
+    from: Option<PathBuf>,
+    database_url: impl FnOnce() -> String,
+) -> Result<Vec<PathBuf>> {
+    let workspace = Workspace::load()?;
+
+    let Some(migrations) = workspace.config.migrations.clone() else {
+        return Err(Error::MigrationsNotConfigured);
+    };
+    let Some(mut desired) = workspace.database.clone() else {
+        return Err(Error::DatabaseNotPulled);
+    };
+    desired.sort();
+
+    if let Some(source) = workspace.config.source {
+        check_source(source)?;
+    }
+
+    let current = match from {
+        Some(path) => diff::load(&path)?,
+        None => {
+            let database_url = database_url();
+            if workspace.config.source.is_none() {
+                check_source(Source::from_url(&database_url)?)?;
+            }
+
+            let Ok(runtime) = Runtime::new() else {
+                return Err(Error::RuntimeInitializationFailed);
+            };
+
+            runtime.block_on(pull::introspect(&workspace, &database_url))?
+        }
+    };
+
+    let diff = Diff::new(&current, &desired);
+    if diff.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // New enum labels can't be used in the transaction that adds them, so they go in a
+    // migration of their own that runs first.
+    let (labels, rest) = diff.split_enum_labels();
+    let parts: Vec<(String, Diff)> = [
+        (format!("{}_enum_labels", file_name(name)), labels),
+        (file_name(name), rest),
+    ]
+    .into_iter()
+    .filter(|(_, diff)| !diff.is_empty())
+    .collect();
+
+    let directory = workspace.path.join(&migrations.path);
+    let mut files = Vec::new();
+    match migrations.format {
+        MigrationFormat::Sqlx => {
+            let now = chrono::Utc::now();
+            for (seconds, (name, diff)) in (0..).zip(&parts) {
+                let prefix = format!(
+                    "{}_{name}",
+                    (now + chrono::TimeDelta::seconds(seconds)).format("%Y%m%d%H%M%S")
+                );
+
+                files.push((format!("{prefix}.up.sql"), diff.to_sql()));
+                files.push((format!("{prefix}.down.sql"), diff.inverse().to_sql()));
+            }
+        }
+        MigrationFormat::Refinery => {
+            let latest = latest_version(&directory)?;
+            for (version, (name, diff)) in (latest + 1..).zip(&parts) {
+                files.push((format!("V{version}__{name}.sql"), diff.to_sql()));
+            }
+        }
+    }
+
+    let mut written = Vec::new();
+    for (file, sql) in files {
+        let path = Path::new(&migrations.path).join(file);
+        workspace.write_file(&path, &sql)?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// Reject the backends that migrations, which are PostgreSQL, can't be written for.
+fn check_source(source: Source) -> Result<()> {
+    match source {
+        Source::Postgres => Ok(()),
+        Source::MySql | Source::Sqlite => Err(Error::UnsupportedMigrationSource(source)),
+    }
+}
+
+/// The highest version among the refinery migrations in the directory, or 0 without any.
+fn latest_version(directory: &Path) -> Result<u64> {
+    if !directory.exists() {
+        return Ok(0);
+    }
+
+    let mut latest = 0;
+    for entry in read_dir(directory)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+
+        let version = name
+            .strip_prefix(['V', 'U'])
+            .and_then(|rest| rest.split_once("__"))
+            .and_then(|(version, _)| version.parse::<u64>().ok());
+        if let Some(version) = version {
+            latest = latest.max(version);
+        }
+    }
+
+    Ok(latest)
+}
+
+/// The migration name as it is used in file names: lowercase, with anything but letters and
+/// digits replaced by underscores.
+fn file_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '_',
+        })
+        .collect()
+}
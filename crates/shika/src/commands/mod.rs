@@ -1,2 +1,6 @@
+pub mod check_compat;
+pub mod diff;
 pub mod generate;
+pub mod migration;
 pub mod pull;
+pub mod status;
@@ -1,6 +1,11 @@
-use std::fmt::Display;
+use std::{fmt::Display, path::Path};
 
 use crate::{DATABASE_FILE_PATH, Result, error::Error};
+use shika_database::{
+    Database, IntrospectOptions, Introspector, MySqlIntrospector, PostgresIntrospector, Source,
+    SqlIntrospector, SqliteIntrospector,
+};
+use shika_workspace::Workspace;
 use tokio::runtime::Runtime;
 
 pub fn command<D: Display>(database_url: D) -> Result<()> {
@@ -13,21 +18,38 @@ pub fn command<D: Display>(database_url: D) -> Result<()> {
     Ok(())
 }
 
+/// Pull the snapshot from a SQL schema dump or a directory of migrations, without connecting
+/// to a database.
+pub fn from_sql<P: AsRef<Path>>(path: P) -> Result<()> {
+    let Ok(runtime) = Runtime::new() else {
+        return Err(Error::RuntimeInitializationFailed);
+    };
+
+    runtime.block_on(async {
+        let workspace = Workspace::load()?;
+
+        let (mut database, skipped) = SqlIntrospector::new(path.as_ref())
+            .introspect_with_skipped(&options(&workspace))
+            .await?;
+        database.sort();
+
+        for statement in skipped {
+            eprintln!("{statement}");
+        }
+
+        workspace.write(DATABASE_FILE_PATH, database)?;
+
+        Ok(())
+    })
+}
+
 fn run_blocking(runtime: Runtime, database_url: String) -> Result<()> {
     runtime.block_on(async {
         // Load the workspace
-        let workspace = shika_workspace::Workspace::load()?;
+        let workspace = Workspace::load()?;
 
         // Load the database
-        let database = shika_database::Database::fetch(
-            &database_url,
-            workspace
-                .config
-                .exclude_tables
-                .clone()
-                .unwrap_or(Vec::new()),
-        )
-        .await?;
+        let database = introspect(&workspace, &database_url).await?;
 
         workspace.write(DATABASE_FILE_PATH, database)?;
 
@@ -37,3 +59,41 @@ fn run_blocking(runtime: Runtime, database_url: String) -> Result<()> {
         Ok(())
     })
 }
+
+/// Introspect the live database with the backend from the config, or else the one matching the
+/// scheme of the URL, and bring the result into canonical order.
+pub async fn introspect(workspace: &Workspace, database_url: &str) -> Result<Database> {
+    let source = match workspace.config.source {
+        Some(source) => source,
+        None => Source::from_url(database_url)?,
+    };
+
+    let options = options(workspace);
+    let mut database = match source {
+        Source::Postgres => {
+            PostgresIntrospector::new(database_url)
+                .introspect(&options)
+                .await?
+        }
+        Source::MySql => {
+            MySqlIntrospector::new(database_url)
+                .introspect(&options)
+                .await?
+        }
+        Source::Sqlite => {
+            SqliteIntrospector::new(database_url)
+                .introspect(&options)
+                .await?
+        }
+    };
+    database.sort();
+
+    Ok(database)
+}
+
+fn options(workspace: &Workspace) -> IntrospectOptions {
+    IntrospectOptions {
+        schemas: workspace.config.schemas.clone().unwrap_or_default(),
+        exclude_tables: workspace.config.exclude_tables.clone().unwrap_or_default(),
+    }
+}
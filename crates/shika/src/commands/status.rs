@@ -0,0 +1,52 @@
+use crate::{Result, commands::pull, error::Error};
+use shika_database::Diff;
+use shika_workspace::Workspace;
+use tokio::runtime::Runtime;
+
+/// Compare the live database with the pulled snapshot of the workspace, without writing
+/// anything, and print the fingerprints of both along with the changes.
+///
+/// Returns whether the database still matches the snapshot, i.e. whether both have the same
+/// fingerprint. The diff doesn't cover everything that goes into the fingerprint, such as
+/// comments or view definitions, so it can be empty while the two still differ.
+pub fn command(database_url: impl FnOnce() -> String) -> Result<bool> {
+    let workspace = Workspace::load()?;
+
+    let Some(mut snapshot) = workspace.database.clone() else {
+        return Err(Error::DatabaseNotPulled);
+    };
+    snapshot.sort();
+
+    let Ok(runtime) = Runtime::new() else {
+        return Err(Error::RuntimeInitializationFailed);
+    };
+    let live = runtime.block_on(pull::introspect(&workspace, &database_url()))?;
+
+    let (expected, actual) = (snapshot.fingerprint()?, live.fingerprint()?);
+    println!("Snapshot: {expected}");
+    println!("Database: {actual}");
+
+    if expected == actual {
+        println!("The database matches the snapshot.");
+        return Ok(true);
+    }
+
+    let diff = Diff::new(&snapshot, &live);
+    if diff.is_empty() {
+        println!(
+            "The database has drifted from the snapshot in details the diff doesn't show, such \
+             as comments, view definitions or identity columns. Pull again to update it."
+        );
+        return Ok(false);
+    }
+
+    println!();
+    print!("{diff}");
+    println!();
+    println!(
+        "The database has drifted from the snapshot by {} change(s).",
+        diff.changes.len()
+    );
+
+    Ok(false)
+}
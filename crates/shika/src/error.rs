@@ -5,6 +5,11 @@ pub enum Error {
     RuntimeInitializationFailed,
     DatabaseNotPulled,
     TemplateNotFound(String),
+    SnapshotNotFound(String),
+    Json(serde_json::Error),
+    IO(std::io::Error),
+    MigrationsNotConfigured,
+    UnsupportedMigrationSource(shika_database::Source),
 }
 
 impl From<shika_database::Error> for Error {
@@ -23,6 +28,18 @@ impl From<shika_renderer::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::IO(err)
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -32,6 +49,19 @@ impl std::fmt::Display for Error {
             Error::Renderer(err) => write!(f, "Renderer error: {err}"),
             Error::RuntimeInitializationFailed => write!(f, "Failed to initialize runtime"),
             Error::TemplateNotFound(template) => write!(f, "Template not found: {template}"),
+            Error::SnapshotNotFound(path) => write!(f, "Snapshot not found: {path}"),
+            Error::Json(err) => write!(f, "JSON error: {err}"),
+            Error::IO(err) => write!(f, "IO error: {err}"),
+            Error::MigrationsNotConfigured => {
+                write!(
+                    f,
+                    "No migrations directory configured in .shika/config.yaml"
+                )
+            }
+            Error::UnsupportedMigrationSource(source) => write!(
+                f,
+                "Migrations are written in PostgreSQL, so they can't be generated for a {source:?} database"
+            ),
         }
     }
 }
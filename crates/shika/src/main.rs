@@ -1,46 +1,95 @@
-use std::process::exit;
+use std::{path::PathBuf, process::exit};
 
 use clap::Parser;
 use dotenvy::dotenv;
-use shika::commands;
+use shika::commands::{self, diff::Format};
 
 #[derive(Parser, Clone, Debug)]
 struct Config {
     #[clap(subcommand)]
     command: Command,
 
+    /// The connection URL, whose scheme selects the database backend. Takes precedence over the
+    /// individual `DB_*` settings, which always connect to PostgreSQL.
     #[arg(long, env)]
-    pub db_name: String,
+    pub database_url: Option<String>,
 
     #[arg(long, env)]
-    pub db_host: String,
+    pub db_name: Option<String>,
 
     #[arg(long, env)]
-    pub db_port: String,
+    pub db_host: Option<String>,
 
     #[arg(long, env)]
-    pub db_user: String,
+    pub db_port: Option<String>,
 
     #[arg(long, env)]
-    pub db_pass: String,
+    pub db_user: Option<String>,
+
+    #[arg(long, env)]
+    pub db_pass: Option<String>,
 }
 
 impl Config {
-    pub fn database_url(&self) -> String {
-        format!(
+    pub fn database_url(&self) -> Option<String> {
+        if let Some(database_url) = &self.database_url {
+            return Some(database_url.clone());
+        }
+
+        Some(format!(
             "postgres://{}:{}@{}:{}/{}",
-            self.db_user, self.db_pass, self.db_host, self.db_port, self.db_name
-        )
+            self.db_user.as_ref()?,
+            self.db_pass.as_ref()?,
+            self.db_host.as_ref()?,
+            self.db_port.as_ref()?,
+            self.db_name.as_ref()?
+        ))
     }
 }
 
 #[derive(clap::Subcommand, Clone, Debug)]
 enum Command {
+    Pull {
+        /// Build the snapshot from a SQL schema dump, or a directory of migrations, instead of
+        /// connecting to the database.
+        #[clap(long, value_name = "FILE_OR_DIR")]
+        from_sql: Option<PathBuf>,
+    },
     Generate {
         template: Option<String>,
         #[clap(long, short, default_value = "false")]
         pull: bool,
     },
+    /// Show what changed between two snapshots. Without arguments, the pulled snapshot is
+    /// compared with the live database.
+    Diff {
+        /// The old snapshot file. Defaults to the pulled snapshot of the workspace.
+        old: Option<PathBuf>,
+        /// The new snapshot file. Defaults to the live database.
+        new: Option<PathBuf>,
+        #[clap(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Check whether the live database still matches the pulled snapshot, without writing
+    /// anything. Exits with an error when it doesn't.
+    Status,
+    /// Classify the changes between two snapshots as safe, risky or breaking, and fail when
+    /// the compatibility policy of the workspace doesn't allow them.
+    CheckCompat {
+        /// The snapshot currently deployed.
+        old: PathBuf,
+        /// The snapshot to deploy.
+        new: PathBuf,
+    },
+    /// Write a SQL migration from the live database to the pulled snapshot, so that an edited
+    /// `.shika/database.yaml` can be applied as the desired state.
+    Migration {
+        /// The name of the migration, e.g. `add_users`.
+        name: String,
+        /// Migrate from this snapshot file instead of the live database.
+        #[clap(long, value_name = "FILE")]
+        from: Option<PathBuf>,
+    },
 }
 
 fn main() {
@@ -49,9 +98,27 @@ fn main() {
     let config = Config::parse();
 
     match config.command {
+        Command::Pull {
+            from_sql: Some(ref path),
+        } => match commands::pull::from_sql(path) {
+            Ok(_) => println!("Successfully pulled the schema from {}.", path.display()),
+            Err(e) => {
+                eprintln!("Failed to pull schema: {e}");
+                exit(1);
+            }
+        },
+        Command::Pull { from_sql: None } => {
+            match commands::pull::command(require_database_url(&config)) {
+                Ok(_) => println!("Successfully pulled the latest changes from the database."),
+                Err(e) => {
+                    eprintln!("Failed to pull database: {e}");
+                    exit(1);
+                }
+            }
+        }
         Command::Generate { ref template, pull } => {
             if pull {
-                match commands::pull::command(config.database_url()) {
+                match commands::pull::command(require_database_url(&config)) {
                     Ok(_) => println!("Successfully pulled the latest changes from the database."),
                     Err(e) => {
                         eprintln!("Failed to pull database: {e}");
@@ -68,5 +135,61 @@ fn main() {
                 }
             }
         }
+        Command::Diff {
+            ref old,
+            ref new,
+            format,
+        } => {
+            let database_url = || require_database_url(&config);
+
+            if let Err(e) = commands::diff::command(old.clone(), new.clone(), database_url, format)
+            {
+                eprintln!("Failed to diff database: {e}");
+                exit(1);
+            }
+        }
+        Command::Status => match commands::status::command(|| require_database_url(&config)) {
+            Ok(true) => {}
+            Ok(false) => exit(1),
+            Err(e) => {
+                eprintln!("Failed to check status: {e}");
+                exit(1);
+            }
+        },
+        Command::CheckCompat { ref old, ref new } => {
+            match commands::check_compat::command(old, new) {
+                Ok(true) => {}
+                Ok(false) => exit(1),
+                Err(e) => {
+                    eprintln!("Failed to check compatibility: {e}");
+                    exit(1);
+                }
+            }
+        }
+        Command::Migration { ref name, ref from } => {
+            let database_url = || require_database_url(&config);
+
+            match commands::migration::command(name, from.clone(), database_url) {
+                Ok(files) if files.is_empty() => println!("No changes."),
+                Ok(files) => {
+                    for file in files {
+                        println!("Created {}", file.display());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to create migration: {e}");
+                    exit(1);
+                }
+            }
+        }
     }
 }
+
+fn require_database_url(config: &Config) -> String {
+    let Some(database_url) = config.database_url() else {
+        eprintln!("Missing database connection: set DATABASE_URL, or all of the DB_* variables.");
+        exit(1);
+    };
+
+    database_url
+}
@@ -0,0 +1,38 @@
+-- A MySQL/MariaDB schema covering keys, enums, indexes, generated columns and comments.
+--
+-- Load it into a local server and pull it with:
+--
+--     mysql -u root -e 'CREATE DATABASE shika_fixture'
+--     mysql -u root shika_fixture < crates/shika_database/fixtures/mysql.sql
+--     DATABASE_URL=mysql://root@localhost:3306/shika_fixture shika pull
+--
+-- `tests/mysql.rs` loads it into a scratch database of the server at SHIKA_TEST_MYSQL_URL:
+--
+--     SHIKA_TEST_MYSQL_URL=mysql://root@localhost:3306 cargo test -p shika_database
+
+CREATE TABLE organizations (
+    id INT NOT NULL,
+    region VARCHAR(16) NOT NULL,
+    name VARCHAR(64) NOT NULL UNIQUE,
+    PRIMARY KEY (id, region)
+) COMMENT = 'Organizations owning members';
+
+CREATE TABLE members (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    organization_id INT NOT NULL,
+    organization_region VARCHAR(16) NOT NULL,
+    mood ENUM('happy', 'sad', 'it''s complicated') NULL,
+    first_name VARCHAR(64) NOT NULL,
+    last_name VARCHAR(64) NOT NULL,
+    full_name VARCHAR(129) GENERATED ALWAYS AS (CONCAT(first_name, ' ', last_name)) STORED,
+    balance DECIMAL(10, 2) NOT NULL DEFAULT 0 COMMENT 'Balance in cents',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY members_name_key (first_name, last_name),
+    KEY members_created_at_idx (created_at),
+    CONSTRAINT members_organization_fkey
+        FOREIGN KEY (organization_id, organization_region)
+        REFERENCES organizations (id, region)
+        ON DELETE CASCADE
+);
+
+CREATE VIEW member_names AS SELECT id, full_name FROM members;
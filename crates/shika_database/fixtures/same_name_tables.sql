@@ -0,0 +1,29 @@
+-- Two schemas that both contain an `accounts` table with different columns and keys.
+--
+-- Pulling with `schemas: [billing, auth]` must produce two separate `accounts` tables,
+-- each with only its own columns and foreign keys. See `same_name_tables.yaml`.
+--
+-- The same snapshot is built offline by `shika pull --from-sql` on this file.
+
+CREATE SCHEMA billing;
+CREATE SCHEMA auth;
+
+CREATE TABLE billing.customers (
+    id integer PRIMARY KEY
+);
+
+CREATE TABLE billing.accounts (
+    id integer PRIMARY KEY,
+    customer_id integer NOT NULL REFERENCES billing.customers (id),
+    balance numeric
+);
+
+CREATE TABLE auth.users (
+    id uuid PRIMARY KEY
+);
+
+CREATE TABLE auth.accounts (
+    id uuid PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES auth.users (id),
+    email text UNIQUE
+);
@@ -0,0 +1,39 @@
+-- A SQLite schema covering keys, indexes, generated columns and views.
+--
+-- Build a database file from it and pull it with:
+--
+--     sqlite3 app.db < crates/shika_database/fixtures/sqlite.sql
+--     DATABASE_URL=sqlite://app.db shika pull
+
+CREATE TABLE organizations (
+    id INTEGER NOT NULL,
+    region TEXT NOT NULL,
+    name VARCHAR(64) NOT NULL UNIQUE,
+    PRIMARY KEY (id, region)
+);
+
+CREATE TABLE members (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    organization_id INTEGER NOT NULL,
+    organization_region TEXT NOT NULL,
+    first_name VARCHAR(64) NOT NULL,
+    last_name VARCHAR(64) NOT NULL,
+    full_name TEXT GENERATED ALWAYS AS (first_name || ' ' || last_name) VIRTUAL,
+    email TEXT,
+    balance DECIMAL(10, 2) NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (first_name, last_name),
+    FOREIGN KEY (organization_id, organization_region)
+        REFERENCES organizations (id, region)
+        ON DELETE CASCADE
+);
+
+CREATE TABLE invitations (
+    id INTEGER PRIMARY KEY,
+    member_id INTEGER REFERENCES members ON DELETE SET NULL
+);
+
+CREATE INDEX members_created_at_idx ON members (created_at);
+CREATE UNIQUE INDEX members_email_key ON members (lower(email)) WHERE email IS NOT NULL;
+
+CREATE VIEW member_names AS SELECT id, full_name FROM members;
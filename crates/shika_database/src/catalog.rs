@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use crate::database::{
+    ForeignKey, ForeignKeyColumn, Index, Reference, ReferentialAction, Table, UniqueConstraint,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConstraintKind {
+    PrimaryKey,
+    Unique,
+    ForeignKey,
+}
+
+/// One column of a key constraint, as every backend reports it: one row per
+/// (constraint, column) pair, in key order.
+#[derive(Debug, Clone)]
+pub(crate) struct KeyColumn {
+    pub name: String,
+    pub kind: ConstraintKind,
+    pub schema: String,
+    pub table: String,
+    pub column: String,
+    /// The referenced column of a foreign key.
+    pub referenced: Option<Reference>,
+    pub on_delete: ReferentialAction,
+    pub on_update: ReferentialAction,
+    pub deferrable: bool,
+    pub initially_deferred: bool,
+}
+
+/// An index together with the table it belongs to.
+#[derive(Debug, Clone)]
+pub(crate) struct TableIndex {
+    pub schema: String,
+    pub table: String,
+    pub index: Index,
+}
+
+/// Stitch the key constraints and indexes of a whole database onto its tables, and derive the
+/// per-column key flags and references from them.
+///
+/// Keys and indexes are grouped by table once up front, so the work grows with the number of
+/// rows rather than with tables times rows.
+pub(crate) fn assemble(tables: &mut [Table], keys: &[KeyColumn], indexes: &[TableIndex]) {
+    let mut keys_by_table: HashMap<(&str, &str), Vec<&KeyColumn>> = HashMap::new();
+    let mut referenced_by: HashMap<(&str, &str, &str), Vec<Reference>> = HashMap::new();
+    for key in keys {
+        keys_by_table
+            .entry((&key.schema, &key.table))
+            .or_default()
+            .push(key);
+
+        if let (ConstraintKind::ForeignKey, Some(r)) = (key.kind, &key.referenced) {
+            referenced_by
+                .entry((&r.schema, &r.table, &r.column))
+                .or_default()
+                .push(Reference {
+                    schema: key.schema.clone(),
+                    table: key.table.clone(),
+                    column: key.column.clone(),
+                });
+        }
+    }
+
+    let mut indexes_by_table: HashMap<(&str, &str), Vec<Index>> = HashMap::new();
+    for index in indexes {
+        indexes_by_table
+            .entry((&index.schema, &index.table))
+            .or_default()
+            .push(index.index.clone());
+    }
+
+    for table in tables.iter_mut() {
+        let owned = keys_by_table
+            .remove(&(table.schema.as_str(), table.name.as_str()))
+            .unwrap_or_default();
+
+        table.primary_key = owned
+            .iter()
+            .filter(|k| k.kind == ConstraintKind::PrimaryKey)
+            .map(|k| k.column.clone())
+            .collect();
+
+        table.foreign_keys = Vec::new();
+        let mut references: HashMap<&str, Reference> = HashMap::new();
+        for key in owned
+            .iter()
+            .filter(|k| k.kind == ConstraintKind::ForeignKey)
+        {
+            let Some(referenced) = &key.referenced else {
+                continue;
+            };
+
+            references
+                .entry(&key.column)
+                .or_insert_with(|| referenced.clone());
+
+            let column = ForeignKeyColumn {
+                column: key.column.clone(),
+                referenced_column: referenced.column.clone(),
+            };
+
+            match table.foreign_keys.iter_mut().find(|fk| fk.name == key.name) {
+                Some(foreign_key) => foreign_key.columns.push(column),
+                None => table.foreign_keys.push(ForeignKey {
+                    name: key.name.clone(),
+                    referenced_schema: referenced.schema.clone(),
+                    referenced_table: referenced.table.clone(),
+                    columns: vec![column],
+                    on_delete: key.on_delete,
+                    on_update: key.on_update,
+                    deferrable: key.deferrable,
+                    initially_deferred: key.initially_deferred,
+                }),
+            }
+        }
+
+        table.unique_constraints = Vec::new();
+        for key in owned.iter().filter(|k| k.kind == ConstraintKind::Unique) {
+            match table
+                .unique_constraints
+                .iter_mut()
+                .find(|u| u.name == key.name)
+            {
+                Some(unique_constraint) => unique_constraint.columns.push(key.column.clone()),
+                None => table.unique_constraints.push(UniqueConstraint {
+                    name: key.name.clone(),
+                    columns: vec![key.column.clone()],
+                }),
+            }
+        }
+
+        table.indexes = indexes_by_table
+            .remove(&(table.schema.as_str(), table.name.as_str()))
+            .unwrap_or_default();
+
+        for column in table.columns.iter_mut() {
+            column.is_primary_key = table.primary_key.contains(&column.name);
+
+            // A column is only unique on its own if a full unique index covers exactly that
+            // column, so a column that is part of a composite `UNIQUE (a, b)` is not.
+            column.is_unique = table.indexes.iter().any(|i| {
+                i.is_unique
+                    && !i.is_primary
+                    && i.predicate.is_none()
+                    && !i.has_expressions()
+                    && i.columns() == [column.name.as_str()]
+            });
+
+            column.references = references.get(column.name.as_str()).cloned();
+
+            column.referenced_by = referenced_by
+                .get(&(
+                    table.schema.as_str(),
+                    table.name.as_str(),
+                    column.name.as_str(),
+                ))
+                .cloned()
+                .unwrap_or_default();
+        }
+    }
+}
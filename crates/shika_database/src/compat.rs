@@ -0,0 +1,193 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diff::Change;
+
+/// How a change affects clients that were built against the other version of the schema,
+/// ordered from harmless to harmful.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Existing queries keep working.
+    Safe,
+    /// Existing queries keep working, but may see data or failures they don't expect, e.g. a
+    /// new enum label or a new unique constraint.
+    Risky,
+    /// Existing queries can fail, e.g. because a column they use is gone.
+    Breaking,
+}
+
+impl Change {
+    /// The names of every kind of change, in the order of `Change`.
+    pub const NAMES: &[&str] = &[
+        "enum_added",
+        "enum_removed",
+        "enum_labels_changed",
+        "table_added",
+        "table_removed",
+        "column_added",
+        "column_removed",
+        "column_type_changed",
+        "column_nullability_changed",
+        "column_default_changed",
+        "primary_key_changed",
+        "foreign_key_added",
+        "foreign_key_removed",
+        "unique_constraint_added",
+        "unique_constraint_removed",
+        "index_added",
+        "index_removed",
+    ];
+
+    /// The name of the kind of change, as used in its serialized `change` tag, e.g.
+    /// `column_removed`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Change::EnumAdded { .. } => "enum_added",
+            Change::EnumRemoved { .. } => "enum_removed",
+            Change::EnumLabelsChanged { .. } => "enum_labels_changed",
+            Change::TableAdded { .. } => "table_added",
+            Change::TableRemoved { .. } => "table_removed",
+            Change::ColumnAdded { .. } => "column_added",
+            Change::ColumnRemoved { .. } => "column_removed",
+            Change::ColumnTypeChanged { .. } => "column_type_changed",
+            Change::ColumnNullabilityChanged { .. } => "column_nullability_changed",
+            Change::ColumnDefaultChanged { .. } => "column_default_changed",
+            Change::PrimaryKeyChanged { .. } => "primary_key_changed",
+            Change::ForeignKeyAdded { .. } => "foreign_key_added",
+            Change::ForeignKeyRemoved { .. } => "foreign_key_removed",
+            Change::UniqueConstraintAdded { .. } => "unique_constraint_added",
+            Change::UniqueConstraintRemoved { .. } => "unique_constraint_removed",
+            Change::IndexAdded { .. } => "index_added",
+            Change::IndexRemoved { .. } => "index_removed",
+        }
+    }
+
+    /// The default severity of the change.
+    ///
+    /// Removing anything that queries may refer to is breaking, and so is anything that makes
+    /// existing writes fail outright: making a column NOT NULL, adding a NOT NULL column
+    /// without a default, or changing the primary key. So is changing a column type, unless
+    /// the new type only widens the old one, e.g. `int4` to `int8` or `varchar(32)` to
+    /// `varchar(64)`. New constraints, widened types and new enum labels are risky, since
+    /// they only affect some rows or values.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Change::EnumAdded { .. }
+            | Change::TableAdded { .. }
+            | Change::ForeignKeyRemoved { .. }
+            | Change::UniqueConstraintRemoved { .. }
+            | Change::IndexRemoved { .. } => Severity::Safe,
+            Change::EnumRemoved { .. }
+            | Change::TableRemoved { .. }
+            | Change::ColumnRemoved { .. }
+            | Change::PrimaryKeyChanged { .. } => Severity::Breaking,
+            Change::EnumLabelsChanged { from, to, .. } => {
+                match from.iter().all(|label| to.contains(label)) {
+                    true => Severity::Risky,
+                    false => Severity::Breaking,
+                }
+            }
+            Change::ColumnAdded { column, .. } => {
+                let is_filled = column.default.is_some()
+                    || column.identity_generation.is_some()
+                    || column.is_generated;
+                match column.required && !is_filled {
+                    true => Severity::Breaking,
+                    false => Severity::Safe,
+                }
+            }
+            Change::ColumnNullabilityChanged { required, .. } => match required {
+                true => Severity::Breaking,
+                false => Severity::Risky,
+            },
+            Change::ColumnDefaultChanged { to, .. } => match to {
+                Some(_) => Severity::Safe,
+                None => Severity::Risky,
+            },
+            Change::ColumnTypeChanged { from, to, .. } => match is_widening(from, to) {
+                true => Severity::Risky,
+                false => Severity::Breaking,
+            },
+            Change::ForeignKeyAdded { .. } | Change::UniqueConstraintAdded { .. } => {
+                Severity::Risky
+            }
+            Change::IndexAdded { index, .. } => match index.is_unique {
+                true => Severity::Risky,
+                false => Severity::Safe,
+            },
+        }
+    }
+}
+
+/// Whether every value of the `from` type is also a value of the `to` type, as written by
+/// `Column::sql_type`, e.g. `int4` to `int8`, `varchar(32)` to `text` or `numeric(8,2)` to
+/// `numeric(10,2)`. Arrays widen when their elements do.
+fn is_widening(from: &str, to: &str) -> bool {
+    let (from, from_modifiers) = split_modifiers(from);
+    let (to, to_modifiers) = split_modifiers(to);
+
+    let dimensions = |name: &str| name.matches("[]").count();
+    if dimensions(&from) != dimensions(&to) {
+        return false;
+    }
+    let from = from.trim_end_matches("[]");
+    let to = to.trim_end_matches("[]");
+
+    let integer_rank = |name: &str| ["int2", "int4", "int8"].iter().position(|n| *n == name);
+    if let (Some(from), Some(to)) = (integer_rank(from), integer_rank(to)) {
+        return from < to;
+    }
+
+    match (from, to) {
+        ("int2" | "int4" | "int8", "numeric") => to_modifiers.is_empty(),
+        ("float4", "float8") => true,
+        ("varchar", "text") => true,
+        ("varchar", "varchar") | ("varbit", "varbit") => {
+            match (&from_modifiers[..], &to_modifiers[..]) {
+                (_, []) => true,
+                ([from], [to]) => from < to,
+                _ => false,
+            }
+        }
+        ("numeric", "numeric") => match (&from_modifiers[..], &to_modifiers[..]) {
+            (_, []) => true,
+            ([], _) => false,
+            (from, to) => {
+                let scale = |modifiers: &[u32]| modifiers.get(1).copied().unwrap_or(0);
+                let (from_scale, to_scale) = (scale(from), scale(to));
+                // Neither the digits after the point nor the ones before it may shrink.
+                to_scale >= from_scale
+                    && to[0].saturating_sub(to_scale) >= from[0].saturating_sub(from_scale)
+            }
+        },
+        _ => false,
+    }
+}
+
+/// Split a type like `numeric(10,2)[]` into `numeric[]` and its modifiers `[10, 2]`.
+fn split_modifiers(sql_type: &str) -> (String, Vec<u32>) {
+    let Some((name, rest)) = sql_type.split_once('(') else {
+        return (sql_type.to_string(), Vec::new());
+    };
+    let Some((modifiers, suffix)) = rest.split_once(')') else {
+        return (sql_type.to_string(), Vec::new());
+    };
+
+    let modifiers = modifiers
+        .split(',')
+        .filter_map(|modifier| modifier.trim().parse().ok())
+        .collect();
+    (format!("{name}{suffix}"), modifiers)
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Safe => f.write_str("safe"),
+            Severity::Risky => f.write_str("risky"),
+            Severity::Breaking => f.write_str("breaking"),
+        }
+    }
+}
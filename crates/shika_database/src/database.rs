@@ -1,20 +1,58 @@
-use std::{fs::File, path::Path};
+use std::{fmt::Display, fs::File, path::Path};
 
-use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
-use sqlx::{Pool, Postgres, query_as};
+use sha2::{Digest, Sha256};
 
 use crate::{Result, error::Error};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Database {
     pub tables: Vec<Table>,
+    #[serde(default)]
+    pub enums: Vec<Enum>,
+}
+
+/// A user-defined enum type, e.g. `CREATE TYPE mood AS ENUM ('happy', 'sad')`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Enum {
+    pub schema: String,
+    pub name: String,
+    /// The labels of the enum, in sort order.
+    pub labels: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Table {
+    pub schema: String,
     pub name: String,
+    #[serde(default)]
+    pub kind: TableKind,
+    /// The `SELECT` statement of a view or materialized view.
+    #[serde(default)]
+    pub definition: Option<String>,
+    /// The `COMMENT ON TABLE` text.
+    #[serde(default)]
+    pub comment: Option<String>,
     pub columns: Vec<Column>,
+    /// The primary key columns, in key order. Empty if the table has no primary key.
+    #[serde(default)]
+    pub primary_key: Vec<String>,
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKey>,
+    #[serde(default)]
+    pub unique_constraints: Vec<UniqueConstraint>,
+    #[serde(default)]
+    pub indexes: Vec<Index>,
+}
+
+/// Views and materialized views are read-only, so templates can skip insert and update code.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TableKind {
+    #[default]
+    Table,
+    View,
+    MaterializedView,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -26,176 +64,275 @@ pub struct Column {
     pub references: Option<Reference>,
     pub is_primary_key: bool,
     pub is_unique: bool,
+    /// The default expression, e.g. `now()` or `nextval('users_id_seq'::regclass)` for serials.
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub is_identity: bool,
+    #[serde(default)]
+    pub identity_generation: Option<IdentityGeneration>,
+    #[serde(default)]
+    pub is_generated: bool,
+    #[serde(default)]
+    pub generation_expression: Option<String>,
+    /// The name of the enum type of the column, if it is one of the `Database::enums`.
+    #[serde(default)]
+    pub enum_name: Option<String>,
+    /// The schema of the enum type of the column, which need not be the schema of its table.
+    #[serde(default)]
+    pub enum_schema: Option<String>,
+    /// The `COMMENT ON COLUMN` text.
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub data_type: DataType,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
-pub struct Reference {
-    pub table: String,
+/// The structured type of a column, with arrays and domains resolved.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DataType {
+    /// The base type, or the element type of an array, e.g. `int4` for an `int4[]` column.
+    pub name: String,
+    /// The underlying type of the column, e.g. `_int4` for an `int4[]` column.
+    pub udt_name: String,
+    /// The number of array dimensions, or 0 if the column is not an array.
+    pub dimensions: i32,
+    /// The maximum length of a character type, e.g. 64 for `varchar(64)`.
+    pub length: Option<i32>,
+    pub precision: Option<i32>,
+    pub scale: Option<i32>,
+    /// The domains wrapping the underlying type, outermost first.
+    pub domains: Vec<String>,
+}
+
+/// Whether an identity column is `GENERATED ALWAYS` or `GENERATED BY DEFAULT`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentityGeneration {
+    Always,
+    ByDefault,
+}
+
+/// A foreign key constraint, which may span multiple columns.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKey {
+    pub name: String,
+    pub referenced_schema: String,
+    pub referenced_table: String,
+    /// The column pairs of the constraint, in key order.
+    pub columns: Vec<ForeignKeyColumn>,
+    #[serde(default)]
+    pub on_delete: ReferentialAction,
+    #[serde(default)]
+    pub on_update: ReferentialAction,
+    #[serde(default)]
+    pub deferrable: bool,
+    #[serde(default)]
+    pub initially_deferred: bool,
+}
+
+/// What happens to referencing rows when the referenced row is deleted or updated.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferentialAction {
+    #[default]
+    NoAction,
+    Restrict,
+    Cascade,
+    SetNull,
+    SetDefault,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKeyColumn {
     pub column: String,
+    pub referenced_column: String,
 }
 
-#[derive(sqlx::FromRow, Debug, Clone)]
-struct TableMetadata {
-    name: String,
+/// A `UNIQUE` constraint, which may span multiple columns.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UniqueConstraint {
+    pub name: String,
+    /// The constrained columns, in key order.
+    pub columns: Vec<String>,
 }
 
-#[derive(sqlx::FromRow, Debug, Clone)]
-struct ColumnMetadata {
-    name: String,
-    kind: String,
-    optional: bool,
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "SerializedIndex")]
+pub struct Index {
+    pub name: String,
+    /// The keys of the index, in key order, e.g. `lower(email)` followed by `tenant_id`.
+    #[serde(with = "serde_yml::with::singleton_map_recursive")]
+    pub keys: Vec<IndexKey>,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    /// The access method, e.g. `btree`, `hash` or `gin`.
+    pub method: String,
+    /// The `WHERE` clause of a partial index.
+    pub predicate: Option<String>,
+}
+
+/// A single key of an index: either a plain column or an expression.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexKey {
+    Column(String),
+    Expression(String),
 }
 
-#[derive(sqlx::FromRow, Debug, Clone)]
-struct ColumnConstraints {
-    is_primary_key: bool,
+/// How an index is read from a snapshot file. Snapshots pulled before keys were ordered list
+/// their column keys and expression keys separately.
+#[derive(Deserialize)]
+struct SerializedIndex {
+    name: String,
+    #[serde(default, with = "serde_yml::with::singleton_map_recursive")]
+    keys: Vec<IndexKey>,
+    #[serde(default)]
+    columns: Vec<String>,
+    #[serde(default)]
+    expressions: Vec<String>,
     is_unique: bool,
+    is_primary: bool,
+    method: String,
+    predicate: Option<String>,
 }
 
-#[derive(sqlx::FromRow, Debug, Clone)]
-struct ColumnReferenceMetadata {
-    column: String,
-    table: String,
+impl From<SerializedIndex> for Index {
+    fn from(index: SerializedIndex) -> Self {
+        let mut keys = index.keys;
+        if keys.is_empty() {
+            keys.extend(index.columns.into_iter().map(IndexKey::Column));
+            keys.extend(index.expressions.into_iter().map(IndexKey::Expression));
+        }
+
+        Self {
+            name: index.name,
+            keys,
+            is_unique: index.is_unique,
+            is_primary: index.is_primary,
+            method: index.method,
+            predicate: index.predicate,
+        }
+    }
+}
+
+impl Index {
+    /// The plain column keys, in key order.
+    pub fn columns(&self) -> Vec<&str> {
+        self.keys
+            .iter()
+            .filter_map(|key| match key {
+                IndexKey::Column(column) => Some(column.as_str()),
+                IndexKey::Expression(_) => None,
+            })
+            .collect()
+    }
+
+    /// Whether any key is an expression rather than a plain column.
+    pub fn has_expressions(&self) -> bool {
+        self.keys
+            .iter()
+            .any(|key| matches!(key, IndexKey::Expression(_)))
+    }
+}
+
+impl Display for IndexKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexKey::Column(column) => f.write_str(column),
+            IndexKey::Expression(expression) => f.write_str(expression),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Reference {
+    pub schema: String,
+    pub table: String,
+    pub column: String,
+}
+
+impl Column {
+    /// The type of the column as it would be written in DDL, e.g. `varchar(64)`,
+    /// `numeric(10,2)` or `int4[]`.
+    pub fn sql_type(&self) -> String {
+        let data_type = &self.data_type;
+
+        // Snapshots pulled before types were structured only have the kind.
+        if data_type.name.is_empty() {
+            return self.kind.clone();
+        }
+
+        let mut sql_type = match (
+            data_type.name.as_str(),
+            data_type.length,
+            data_type.precision,
+        ) {
+            ("varchar" | "bpchar" | "char" | "bit" | "varbit", Some(length), _) => {
+                format!("{}({length})", data_type.name)
+            }
+            ("numeric" | "decimal", _, Some(precision)) => match data_type.scale {
+                Some(scale) => format!("{}({precision},{scale})", data_type.name),
+                None => format!("{}({precision})", data_type.name),
+            },
+            (name, ..) => name.to_string(),
+        };
+        for _ in 0..data_type.dimensions {
+            sql_type.push_str("[]");
+        }
+
+        sql_type
+    }
+
+    /// The SQL type of the column with an enum type qualified by its schema, e.g.
+    /// `billing.status[]`, and otherwise the same as `sql_type`.
+    pub fn qualified_sql_type(&self) -> String {
+        match (&self.enum_schema, &self.enum_name) {
+            (Some(schema), Some(_)) => format!("{schema}.{}", self.sql_type()),
+            _ => self.sql_type(),
+        }
+    }
 }
 
 impl Database {
-    pub async fn fetch(database_url: &str, ignore: Vec<String>) -> Result<Self> {
-        let connection = Pool::<Postgres>::connect(database_url)
-            .await
-            .map_err(Error::Connection)?;
-
-        let mut tables_stream = query_as::<_, TableMetadata>(
-            r#"
-                SELECT
-                    "table_name" AS "name"
-                FROM "information_schema"."tables"
-                WHERE
-                    "table_type" = 'BASE TABLE'
-                    AND "table_schema" = $1
-                    AND NOT ("table_name" = ANY($2))
-            "#,
-        )
-        .bind("public")
-        .bind(ignore)
-        .fetch(&connection);
-
-        let mut tables: Vec<Table> = Vec::new();
-        while let Some(table) = tables_stream.try_next().await? {
-            let mut columns_stream = query_as::<_, ColumnMetadata>(
-                r#"
-                    SELECT
-                        C."column_name" AS "name",
-                        C."data_type" AS "kind",
-                        CASE
-                            WHEN C."is_nullable" = 'YES' THEN TRUE
-                        ELSE
-                            FALSE
-                        END AS "optional"
-                    FROM "information_schema"."columns" AS C
-                    WHERE C."table_name" = $1
-                "#,
-            )
-            .bind(&table.name)
-            .fetch(&connection);
-
-            let mut columns: Vec<Column> = Vec::new();
-            while let Some(column) = columns_stream.try_next().await? {
-                let constraints = query_as::<_, ColumnConstraints>(
-                    r#"
-                        SELECT
-                            CASE
-                                WHEN TC."constraint_type" = 'PRIMARY KEY' THEN TRUE
-                            ELSE
-                                FALSE
-                            END AS "is_primary_key",
-                            CASE
-                                WHEN TC."constraint_type" = 'UNIQUE' THEN TRUE
-                            ELSE
-                                FALSE
-                            END AS "is_unique",
-                            TC.*
-                        FROM "information_schema"."key_column_usage" AS KCU
-                        LEFT JOIN "information_schema"."table_constraints" AS TC
-                            ON TC.table_name = KCU.table_name
-                            AND TC.constraint_catalog = KCU.constraint_catalog
-                            AND TC.constraint_schema = KCU.constraint_schema
-                            AND TC.constraint_name = KCU.constraint_name
-                        WHERE KCU."column_name" = $1 AND KCU."table_name" = $2
-                    "#,
-                )
-                .bind(&column.name)
-                .bind(&table.name)
-                .fetch_all(&connection)
-                .await?;
-
-                let is_primary_key = constraints.iter().any(|c| c.is_primary_key);
-                let is_unique = constraints.iter().any(|c| c.is_unique);
-
-                // Query for all columns referencing this column.
-                let referenced_by = query_as::<_, ColumnReferenceMetadata>(
-                    r#"
-                        SELECT
-                            REFBY."column_name" AS "column",
-                            REFBY."table_name" AS "table"
-                        FROM "information_schema"."referential_constraints" AS RC
-                        INNER JOIN "information_schema"."key_column_usage" AS REFBY
-                            ON RC."constraint_name" = REFBY."constraint_name"
-                        INNER JOIN "information_schema"."key_column_usage" AS REFTO
-                            ON RC."unique_constraint_name" = REFTO."constraint_name"
-                        WHERE REFTO."column_name" = $1 AND REFTO."table_name" = $2
-                    "#,
-                )
-                .bind(&column.name)
-                .bind(&table.name)
-                .fetch_all(&connection)
-                .await?;
-
-                // Query for all columns referenced by this column.
-                let references = query_as::<_, ColumnReferenceMetadata>(
-                    r#"
-                        SELECT
-                            REFTO."column_name" AS "column",
-                            REFTO."table_name" AS "table"
-                        FROM "information_schema"."referential_constraints" AS RC
-                        INNER JOIN "information_schema"."key_column_usage" AS REFBY
-                            ON RC."constraint_name" = REFBY."constraint_name"
-                        INNER JOIN "information_schema"."key_column_usage" AS REFTO
-                            ON RC."unique_constraint_name" = REFTO."constraint_name"
-                        WHERE REFBY."column_name" = $1 AND REFBY."table_name" = $2
-                    "#,
-                )
-                .bind(&column.name)
-                .bind(&table.name)
-                .fetch_optional(&connection)
-                .await?;
-
-                columns.push(Column {
-                    name: column.name,
-                    kind: column.kind,
-                    is_primary_key,
-                    is_unique,
-                    required: !column.optional,
-                    referenced_by: referenced_by
-                        .into_iter()
-                        .map(|r| Reference {
-                            table: r.table,
-                            column: r.column,
-                        })
-                        .collect(),
-                    references: references.map(|r| Reference {
-                        table: r.table,
-                        column: r.column,
-                    }),
+    /// Bring the snapshot into its canonical order, so that pulling an unchanged database
+    /// always serializes to the same bytes.
+    ///
+    /// Tables and enums are sorted by schema and name, and constraints and indexes by name.
+    /// Columns keep their ordinal position, and key columns keep their key order.
+    pub fn sort(&mut self) {
+        self.tables
+            .sort_by(|a, b| (&a.schema, &a.name).cmp(&(&b.schema, &b.name)));
+        self.enums
+            .sort_by(|a, b| (&a.schema, &a.name).cmp(&(&b.schema, &b.name)));
+
+        for table in &mut self.tables {
+            table.foreign_keys.sort_by(|a, b| a.name.cmp(&b.name));
+            table.unique_constraints.sort_by(|a, b| a.name.cmp(&b.name));
+            table.indexes.sort_by(|a, b| a.name.cmp(&b.name));
+
+            for column in &mut table.columns {
+                column.referenced_by.sort_by(|a, b| {
+                    (&a.schema, &a.table, &a.column).cmp(&(&b.schema, &b.table, &b.column))
                 });
             }
-
-            tables.push(Table {
-                name: table.name.clone(),
-                columns,
-            });
         }
+    }
+
+    /// A SHA-256 hash of the snapshot in canonical order, formatted as `sha256:<hex>`.
+    ///
+    /// Two snapshots have the same fingerprint exactly when they pull to the same file.
+    pub fn fingerprint(&self) -> Result<String> {
+        let mut database = self.clone();
+        database.sort();
+
+        let yaml = serde_yml::to_string(&database).map_err(Error::InvalidDatabaseFile)?;
+        let hash = Sha256::digest(yaml.as_bytes());
 
-        Ok(Database { tables })
+        Ok(format!(
+            "sha256:{}",
+            hash.iter().map(|b| format!("{b:02x}")).collect::<String>()
+        ))
     }
 
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
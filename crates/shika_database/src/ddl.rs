@@ -0,0 +1,1570 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use sqlparser::{
+    ast::{
+        self, AlterColumnOperation, AlterTableOperation, ArrayElemTypeDef, CharacterLength,
+        ColumnDef, ColumnOption, CommentDef, CommentObject, ConstraintCharacteristics,
+        DeferrableInitial, ExactNumberInfo, Expr, Ident, ObjectName, ObjectType, Statement,
+        TableConstraint, TimezoneInfo, UserDefinedTypeRepresentation,
+    },
+    dialect::PostgreSqlDialect,
+    keywords::Keyword,
+    parser::{Parser, ParserError},
+    tokenizer::Token,
+};
+
+use crate::{
+    Result,
+    catalog::{self, ConstraintKind, KeyColumn, TableIndex},
+    database::{
+        Column, DataType, Database, Enum, ForeignKey, ForeignKeyColumn, IdentityGeneration, Index,
+        IndexKey, Reference, ReferentialAction, Table, TableKind, UniqueConstraint,
+    },
+    error::Error,
+    introspector::{IntrospectOptions, Introspector},
+};
+
+const DEFAULT_SCHEMA: &str = "public";
+
+/// The statements that shape the snapshot. Everything else in a dump or migration, such as
+/// grants, functions or data, is skipped without being parsed.
+const STATEMENTS: &[&str] = &[
+    "CREATE TABLE",
+    "CREATE UNLOGGED TABLE",
+    "CREATE TYPE",
+    "CREATE DOMAIN",
+    "CREATE INDEX",
+    "CREATE UNIQUE INDEX",
+    "CREATE VIEW",
+    "CREATE OR REPLACE VIEW",
+    "CREATE MATERIALIZED VIEW",
+    "ALTER TABLE",
+    "ALTER TYPE",
+    "COMMENT ON TABLE",
+    "COMMENT ON COLUMN",
+    "DROP TABLE",
+    "DROP TYPE",
+    "DROP DOMAIN",
+    "DROP INDEX",
+    "DROP VIEW",
+    "DROP MATERIALIZED VIEW",
+];
+
+/// Builds a snapshot offline from PostgreSQL DDL, e.g. a `pg_dump --schema-only` file or a
+/// directory of migrations.
+///
+/// The statements are applied in order, so later migrations can alter or drop what earlier ones
+/// created. A directory is searched recursively for `.sql` files, which are applied in natural
+/// order of their paths, so `V10__x.sql` comes after `V9__x.sql`. Down migrations, named
+/// `down.sql` or `*.down.sql`, are skipped.
+///
+/// A statement that can't be parsed is skipped, so one unsupported form in a dump doesn't fail
+/// the whole pull. [`SqlIntrospector::introspect_with_skipped`] returns the skipped statements
+/// along with the snapshot.
+#[derive(Debug, Clone)]
+pub struct SqlIntrospector {
+    path: PathBuf,
+}
+
+/// A statement that was left out of the snapshot because it can't be parsed.
+#[derive(Debug, Clone)]
+pub struct SkippedStatement {
+    pub file: PathBuf,
+    /// The line the statement starts on, counting from 1.
+    pub line: usize,
+    pub error: String,
+}
+
+impl Display for SkippedStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Skipped a statement at {}:{} that can't be parsed: {}",
+            self.file.display(),
+            self.line,
+            self.error
+        )
+    }
+}
+
+/// A `CREATE DOMAIN`, which columns use in place of the type it wraps.
+struct Domain {
+    schema: String,
+    name: String,
+    data_type: ast::DataType,
+    is_not_null: bool,
+}
+
+impl SqlIntrospector {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Build the snapshot, along with the statements that were skipped.
+    pub async fn introspect_with_skipped(
+        &self,
+        options: &IntrospectOptions,
+    ) -> Result<(Database, Vec<SkippedStatement>)> {
+        let mut database = Database {
+            tables: Vec::new(),
+            enums: Vec::new(),
+        };
+        let mut domains = Vec::new();
+        let mut skipped = Vec::new();
+
+        for file in sql_files(&self.path)? {
+            let sql = fs::read_to_string(&file).map_err(Error::IO)?;
+
+            for statement in split_statements(&sql) {
+                let keywords = statement
+                    .split_whitespace()
+                    .take(4)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .to_ascii_uppercase();
+                if !STATEMENTS.iter().any(|s| keywords.starts_with(s)) {
+                    continue;
+                }
+
+                let mut skip = |error: ParserError| {
+                    // The statement is a slice of the file, so its offset gives its line.
+                    let offset = statement.as_ptr() as usize - sql.as_ptr() as usize;
+                    skipped.push(SkippedStatement {
+                        file: file.clone(),
+                        line: sql[..offset].matches('\n').count() + 1,
+                        error: error.to_string(),
+                    });
+                };
+
+                // sqlparser has neither `ALTER TYPE`, which migrations use to add enum labels,
+                // nor domains.
+                let handled = match &keywords {
+                    k if k.starts_with("ALTER TYPE") => {
+                        Some(add_enum_label(&mut database, statement))
+                    }
+                    k if k.starts_with("CREATE DOMAIN") => {
+                        Some(create_domain(&mut domains, statement))
+                    }
+                    k if k.starts_with("DROP DOMAIN") => Some(drop_domain(&mut domains, statement)),
+                    _ => None,
+                };
+                if let Some(result) = handled {
+                    if let Err(error) = result {
+                        skip(error);
+                    }
+                    continue;
+                }
+                if is_table_setting(statement) {
+                    continue;
+                }
+
+                let normalized = strip_identity_options(&normalize(statement));
+                match Parser::parse_sql(&PostgreSqlDialect {}, &normalized) {
+                    Ok(statements) => {
+                        for statement in statements {
+                            apply(&mut database, &domains, statement);
+                        }
+                    }
+                    Err(error) => skip(error),
+                }
+            }
+        }
+
+        Ok((finish(database, options), skipped))
+    }
+}
+
+impl Introspector for SqlIntrospector {
+    async fn introspect(&self, options: &IntrospectOptions) -> Result<Database> {
+        let (database, _) = self.introspect_with_skipped(options).await?;
+
+        Ok(database)
+    }
+}
+
+/// Collect the `.sql` files at the path, in the order they should be applied.
+fn sql_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    let mut directories = vec![path.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        for entry in fs::read_dir(&directory).map_err(Error::IO)? {
+            let path = entry.map_err(Error::IO)?.path();
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+
+            if path.is_dir() {
+                directories.push(path);
+            } else if name.ends_with(".sql") && name != "down.sql" && !name.ends_with(".down.sql") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort_by(|a, b| natural_order(&a.to_string_lossy(), &b.to_string_lossy()));
+
+    Ok(files)
+}
+
+/// Compare two strings with runs of digits compared by their numeric value.
+fn natural_order(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let mut x = String::new();
+                while let Some(c) = a.next_if(char::is_ascii_digit) {
+                    x.push(c);
+                }
+                let mut y = String::new();
+                while let Some(c) = b.next_if(char::is_ascii_digit) {
+                    y.push(c);
+                }
+
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                match x.len().cmp(&y.len()).then_with(|| x.cmp(y)) {
+                    Ordering::Equal => {}
+                    ordering => return ordering,
+                }
+            }
+            (Some(x), Some(y)) => match x.cmp(&y) {
+                Ordering::Equal => {
+                    a.next();
+                    b.next();
+                }
+                ordering => return ordering,
+            },
+        }
+    }
+}
+
+/// Split a script into its statements on the semicolons outside of quotes, dollar quotes and
+/// comments. Leading comments and psql meta-commands such as `\restrict` are dropped.
+fn split_statements(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    let at_start = |start: usize, i: usize| sql[start..i].trim().is_empty();
+
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                let leading = at_start(start, i);
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                if leading {
+                    start = i;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let leading = at_start(start, i);
+                i = sql[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + 2 + end + 1);
+                if leading {
+                    start = (i + 1).min(bytes.len());
+                }
+            }
+            b'\\' if at_start(start, i) => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                start = i;
+            }
+            b'$' => {
+                if let Some(tag) = dollar_quote(&sql[i..]) {
+                    let body = i + tag.len();
+                    i = sql[body..]
+                        .find(tag)
+                        .map_or(bytes.len(), |end| body + end + tag.len() - 1);
+                }
+            }
+            b';' => {
+                statements.push(&sql[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+    statements.push(&sql[start.min(sql.len())..]);
+
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Remove the sequence options of identity columns, e.g. the `(SEQUENCE NAME users_id_seq ...)`
+/// that `pg_dump` writes after `AS IDENTITY`, which sqlparser can't parse and which don't affect
+/// the snapshot.
+fn strip_identity_options(statement: &str) -> String {
+    let mut statement = statement.to_string();
+    let mut from = 0;
+
+    // ASCII uppercasing keeps every character at its byte offset, unlike `to_uppercase`, which
+    // changes the length of characters like `ﬁ`.
+    while let Some(position) = statement[from..].to_ascii_uppercase().find("AS IDENTITY") {
+        let end = from + position + "AS IDENTITY".len();
+        from = end;
+
+        let rest = &statement[end..];
+        if !rest.trim_start().starts_with('(') {
+            continue;
+        }
+        let open = end + rest.find('(').unwrap_or_default();
+
+        let mut depth = 0;
+        for (offset, c) in statement[open..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                statement.replace_range(open..=open + offset, "");
+                break;
+            }
+        }
+    }
+
+    statement
+}
+
+/// Rewrite the PostgreSQL forms sqlparser can't parse into ones it can, where the difference
+/// doesn't show in the snapshot: `CREATE UNLOGGED TABLE`, `DROP MATERIALIZED VIEW`, a trailing
+/// `NOT VALID` on a constraint and a trailing `WITH [NO] DATA` on a materialized view.
+fn normalize(statement: &str) -> String {
+    if let Some(rest) = strip_leading_keywords(statement, "CREATE UNLOGGED TABLE") {
+        return format!("CREATE TABLE{rest}");
+    }
+    // A view and a materialized view can't share a name, so dropping either is the same.
+    if let Some(rest) = strip_leading_keywords(statement, "DROP MATERIALIZED VIEW") {
+        return format!("DROP VIEW{rest}");
+    }
+
+    for keywords in ["NOT VALID", "WITH NO DATA", "WITH DATA"] {
+        if let Some(rest) = strip_trailing_keywords(statement, keywords) {
+            return rest.to_string();
+        }
+    }
+
+    statement.to_string()
+}
+
+/// The rest of a statement after its leading keywords, matched case-insensitively across any
+/// whitespace, or `None` if it doesn't start with them.
+fn strip_leading_keywords<'a>(statement: &'a str, keywords: &str) -> Option<&'a str> {
+    let mut rest = statement;
+    for keyword in keywords.split(' ') {
+        rest = rest.trim_start();
+        let head = rest.get(..keyword.len())?;
+        if !head.eq_ignore_ascii_case(keyword) || rest[keyword.len()..].starts_with(is_word) {
+            return None;
+        }
+        rest = &rest[keyword.len()..];
+    }
+
+    Some(rest)
+}
+
+/// The statement without its trailing keywords, or `None` if it doesn't end with them.
+fn strip_trailing_keywords<'a>(statement: &'a str, keywords: &str) -> Option<&'a str> {
+    let mut rest = statement;
+    for keyword in keywords.rsplit(' ') {
+        rest = rest.trim_end();
+        let start = rest.len().checked_sub(keyword.len())?;
+        let tail = rest.get(start..)?;
+        if !tail.eq_ignore_ascii_case(keyword) || rest[..start].ends_with(is_word) {
+            return None;
+        }
+        rest = &rest[..start];
+    }
+
+    Some(rest)
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether a statement is an `ALTER TABLE` that only changes how a table is stored, replicated
+/// or partitioned, e.g. `REPLICA IDENTITY FULL`, `CLUSTER ON`, `SET (fillfactor=70)` or
+/// `ATTACH PARTITION`. sqlparser can't parse these, and they don't show in the snapshot.
+fn is_table_setting(sql: &str) -> bool {
+    let dialect = PostgreSqlDialect {};
+    let Ok(mut parser) = Parser::new(&dialect).try_with_sql(sql) else {
+        return false;
+    };
+
+    if !parser.parse_keywords(&[Keyword::ALTER, Keyword::TABLE]) {
+        return false;
+    }
+    let _ = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+    let _ = parser.parse_keyword(Keyword::ONLY);
+    if parser.parse_object_name(false).is_err() {
+        return false;
+    }
+
+    parser.parse_keywords(&[Keyword::REPLICA, Keyword::IDENTITY])
+        || parser.parse_keywords(&[Keyword::CLUSTER, Keyword::ON])
+        || parser.parse_keywords(&[Keyword::SET, Keyword::WITHOUT, Keyword::CLUSTER])
+        || parser.parse_keywords(&[Keyword::ATTACH, Keyword::PARTITION])
+        || parser.parse_keywords(&[Keyword::DETACH, Keyword::PARTITION])
+        || (parser.parse_keyword(Keyword::SET) && parser.peek_token().token == Token::LParen)
+}
+
+/// The opening tag of a dollar-quoted string, e.g. `$$` or `$body$`.
+fn dollar_quote(sql: &str) -> Option<&str> {
+    let end = sql[1..].find('$')? + 1;
+    let tag = &sql[1..end];
+
+    let valid = tag.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !tag.starts_with(|c: char| c.is_ascii_digit());
+
+    valid.then(|| &sql[..=end])
+}
+
+/// Apply `ALTER TYPE <name> ADD VALUE [IF NOT EXISTS] '<label>' [{BEFORE | AFTER} '<label>']`.
+fn add_enum_label(database: &mut Database, sql: &str) -> std::result::Result<(), ParserError> {
+    let dialect = PostgreSqlDialect {};
+    let mut parser = Parser::new(&dialect).try_with_sql(sql)?;
+
+    if !parser.parse_keywords(&[Keyword::ALTER, Keyword::TYPE]) {
+        return Ok(());
+    }
+    let (schema, name) = qualified(&parser.parse_object_name(false)?);
+    if !parser.parse_keywords(&[Keyword::ADD, Keyword::VALUE]) {
+        return Ok(());
+    }
+    let _ = parser.parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+    let label = parser.parse_literal_string()?;
+
+    let Some(enumeration) = database
+        .enums
+        .iter_mut()
+        .find(|e| e.schema == schema && e.name == name)
+    else {
+        return Ok(());
+    };
+    if enumeration.labels.contains(&label) {
+        return Ok(());
+    }
+
+    let position = if parser.parse_keyword(Keyword::BEFORE) {
+        let before = parser.parse_literal_string()?;
+        enumeration.labels.iter().position(|l| *l == before)
+    } else if parser.parse_keyword(Keyword::AFTER) {
+        let after = parser.parse_literal_string()?;
+        enumeration
+            .labels
+            .iter()
+            .position(|l| *l == after)
+            .map(|p| p + 1)
+    } else {
+        None
+    };
+
+    match position {
+        Some(position) => enumeration.labels.insert(position, label),
+        None => enumeration.labels.push(label),
+    }
+
+    Ok(())
+}
+
+/// Apply `CREATE DOMAIN <name> [AS] <type> [<constraint> ...]`, keeping whether one of the
+/// constraints is `NOT NULL`. Defaults and checks don't show in the snapshot.
+fn create_domain(domains: &mut Vec<Domain>, sql: &str) -> std::result::Result<(), ParserError> {
+    let dialect = PostgreSqlDialect {};
+    let mut parser = Parser::new(&dialect).try_with_sql(sql)?;
+
+    parser.expect_keyword(Keyword::CREATE)?;
+    expect_word(&mut parser, "DOMAIN")?;
+    let (schema, name) = qualified(&parser.parse_object_name(false)?);
+    let _ = parser.parse_keyword(Keyword::AS);
+    let data_type = parser.parse_data_type()?;
+
+    // `NOT NULL` outside the parentheses of a check, e.g. not `CHECK (VALUE IS NOT NULL)`.
+    let mut is_not_null = false;
+    let mut depth = 0;
+    loop {
+        match parser.next_token().token {
+            Token::EOF => break,
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            Token::Word(word) if depth == 0 && word.keyword == Keyword::NOT => {
+                is_not_null |= parser.parse_keyword(Keyword::NULL);
+            }
+            _ => {}
+        }
+    }
+
+    domains.retain(|d| !(d.schema == schema && d.name == name));
+    domains.push(Domain {
+        schema,
+        name,
+        data_type,
+        is_not_null,
+    });
+
+    Ok(())
+}
+
+/// Apply `DROP DOMAIN [IF EXISTS] <name> [, ...] [CASCADE | RESTRICT]`.
+fn drop_domain(domains: &mut Vec<Domain>, sql: &str) -> std::result::Result<(), ParserError> {
+    let dialect = PostgreSqlDialect {};
+    let mut parser = Parser::new(&dialect).try_with_sql(sql)?;
+
+    parser.expect_keyword(Keyword::DROP)?;
+    expect_word(&mut parser, "DOMAIN")?;
+    let _ = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+    for name in parser.parse_comma_separated(|parser| parser.parse_object_name(false))? {
+        let (schema, name) = qualified(&name);
+        domains.retain(|d| !(d.schema == schema && d.name == name));
+    }
+
+    Ok(())
+}
+
+/// Expect a word that sqlparser has no keyword for, such as `DOMAIN`.
+fn expect_word(parser: &mut Parser, expected: &str) -> std::result::Result<(), ParserError> {
+    let token = parser.next_token();
+    match &token.token {
+        Token::Word(word) if word.value.eq_ignore_ascii_case(expected) => Ok(()),
+        _ => parser.expected(expected, token),
+    }
+}
+
+fn apply(database: &mut Database, domains: &[Domain], statement: Statement) {
+    match statement {
+        Statement::CreateType {
+            name,
+            representation: UserDefinedTypeRepresentation::Enum { labels },
+        } => {
+            let (schema, name) = qualified(&name);
+            database
+                .enums
+                .retain(|e| !(e.schema == schema && e.name == name));
+            database.enums.push(Enum {
+                schema,
+                name,
+                labels: labels.into_iter().map(|l| l.value).collect(),
+            });
+        }
+        Statement::CreateTable(create) => {
+            let (schema, name) = qualified(&create.name);
+            if create.if_not_exists && find_table(&database.tables, &schema, &name).is_some() {
+                return;
+            }
+
+            let mut table = empty_table(schema, name, TableKind::Table);
+            table.comment = create.comment.map(|comment| match comment {
+                CommentDef::WithEq(comment)
+                | CommentDef::WithoutEq(comment)
+                | CommentDef::AfterColumnDefsWithoutEq(comment) => comment,
+            });
+            for column in &create.columns {
+                add_column(&mut table, &database.enums, domains, column);
+            }
+            for constraint in &create.constraints {
+                add_constraint(&mut table, constraint);
+            }
+
+            database
+                .tables
+                .retain(|t| !(t.schema == table.schema && t.name == table.name));
+            database.tables.push(table);
+        }
+        Statement::CreateView {
+            or_replace,
+            materialized,
+            name,
+            columns,
+            query,
+            ..
+        } => {
+            let (schema, name) = qualified(&name);
+            if !or_replace && find_table(&database.tables, &schema, &name).is_some() {
+                return;
+            }
+
+            let kind = match materialized {
+                true => TableKind::MaterializedView,
+                false => TableKind::View,
+            };
+            let mut view = empty_table(schema, name, kind);
+            view.columns = view_columns(&database.tables, &view.schema, &query);
+            if columns.len() == view.columns.len() {
+                for (column, alias) in view.columns.iter_mut().zip(&columns) {
+                    column.name = ident(&alias.name);
+                }
+            }
+            view.definition = Some(query.to_string());
+
+            database
+                .tables
+                .retain(|t| !(t.schema == view.schema && t.name == view.name));
+            database.tables.push(view);
+        }
+        Statement::CreateIndex(create) => {
+            let (schema, table_name) = qualified(&create.table_name);
+            let Some(table) = find_table_mut(&mut database.tables, &schema, &table_name) else {
+                return;
+            };
+
+            let keys: Vec<IndexKey> = create
+                .columns
+                .iter()
+                .map(|key| match &key.expr {
+                    Expr::Identifier(column) => IndexKey::Column(ident(column)),
+                    expression => IndexKey::Expression(expression.to_string()),
+                })
+                .collect();
+
+            let name = match &create.name {
+                Some(name) => qualified(name).1,
+                None => {
+                    let columns: Vec<&str> = keys
+                        .iter()
+                        .filter_map(|key| match key {
+                            IndexKey::Column(column) => Some(column.as_str()),
+                            IndexKey::Expression(_) => None,
+                        })
+                        .collect();
+                    format!("{}_{}_idx", table.name, columns.join("_"))
+                }
+            };
+            if table.indexes.iter().any(|i| i.name == name) {
+                return;
+            }
+
+            table.indexes.push(Index {
+                name,
+                keys,
+                is_unique: create.unique,
+                is_primary: false,
+                method: create
+                    .using
+                    .as_ref()
+                    .map_or("btree".to_string(), |using| using.value.to_lowercase()),
+                predicate: create.predicate.as_ref().map(ToString::to_string),
+            });
+        }
+        Statement::AlterTable {
+            name, operations, ..
+        } => {
+            let (schema, name) = qualified(&name);
+            for operation in operations {
+                alter_table(database, domains, &schema, &name, operation);
+            }
+        }
+        Statement::Comment {
+            object_type,
+            object_name,
+            comment,
+            ..
+        } => {
+            let mut parts: Vec<String> = object_name.0.iter().map(ident).collect();
+            match object_type {
+                CommentObject::Table => {
+                    let (schema, name) = qualified(&object_name);
+                    if let Some(table) = find_table_mut(&mut database.tables, &schema, &name) {
+                        table.comment = comment;
+                    }
+                }
+                CommentObject::Column if parts.len() >= 2 => {
+                    let column = parts.pop().unwrap_or_default();
+                    let name = parts.pop().unwrap_or_default();
+                    let schema = parts.pop().unwrap_or(DEFAULT_SCHEMA.to_string());
+
+                    if let Some(column) = find_table_mut(&mut database.tables, &schema, &name)
+                        .and_then(|t| t.columns.iter_mut().find(|c| c.name == column))
+                    {
+                        column.comment = comment;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Statement::Drop {
+            object_type, names, ..
+        } => {
+            for name in &names {
+                let (schema, name) = qualified(name);
+                match object_type {
+                    ObjectType::Table | ObjectType::View => database
+                        .tables
+                        .retain(|t| !(t.schema == schema && t.name == name)),
+                    ObjectType::Type => database
+                        .enums
+                        .retain(|e| !(e.schema == schema && e.name == name)),
+                    ObjectType::Index => {
+                        for table in database.tables.iter_mut().filter(|t| t.schema == schema) {
+                            table.indexes.retain(|i| i.name != name);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Resolve the columns of a view selecting plain columns from tables, e.g.
+/// `SELECT u.id, u.email AS login FROM users AS u`. Computed columns can't be typed without a
+/// database, so they are left out.
+fn view_columns(tables: &[Table], schema: &str, query: &ast::Query) -> Vec<Column> {
+    let ast::SetExpr::Select(select) = query.body.as_ref() else {
+        return Vec::new();
+    };
+
+    // Every table in the FROM clause, by its alias or its name.
+    let mut sources: Vec<(String, &Table)> = Vec::new();
+    for relation in select.from.iter().flat_map(|from| {
+        std::iter::once(&from.relation).chain(from.joins.iter().map(|j| &j.relation))
+    }) {
+        if let ast::TableFactor::Table { name, alias, .. } = relation {
+            let (table_schema, table_name) = match name.0.len() {
+                1 => (schema.to_string(), ident(&name.0[0])),
+                _ => qualified(name),
+            };
+            if let Some(table) = find_table(tables, &table_schema, &table_name) {
+                let alias = alias.as_ref().map_or(table_name, |a| ident(&a.name));
+                sources.push((alias, table));
+            }
+        }
+    }
+
+    let find = |source: Option<&str>, column: &str| {
+        sources
+            .iter()
+            .filter(|(alias, _)| source.is_none_or(|source| source == alias))
+            .find_map(|(_, table)| table.columns.iter().find(|c| c.name == column))
+    };
+    let resolve = |expression: &Expr| match expression {
+        Expr::Identifier(column) => find(None, &ident(column)),
+        Expr::CompoundIdentifier(parts) if parts.len() >= 2 => find(
+            Some(&ident(&parts[parts.len() - 2])),
+            &ident(&parts[parts.len() - 1]),
+        ),
+        _ => None,
+    };
+
+    let mut columns = Vec::new();
+    for item in &select.projection {
+        match item {
+            ast::SelectItem::UnnamedExpr(expression) => {
+                columns.extend(resolve(expression).cloned())
+            }
+            ast::SelectItem::ExprWithAlias { expr, alias } => {
+                columns.extend(resolve(expr).cloned().map(|column| Column {
+                    name: ident(alias),
+                    ..column
+                }))
+            }
+            ast::SelectItem::Wildcard(_) => {
+                columns.extend(sources.iter().flat_map(|(_, t)| t.columns.iter().cloned()))
+            }
+            ast::SelectItem::QualifiedWildcard(name, _) => {
+                let alias = qualified(name).1;
+                columns.extend(
+                    sources
+                        .iter()
+                        .filter(|(a, _)| *a == alias)
+                        .flat_map(|(_, t)| t.columns.iter().cloned()),
+                )
+            }
+        }
+    }
+
+    // View columns carry the type of the underlying column, but none of its constraints.
+    columns
+        .into_iter()
+        .map(|column| Column {
+            required: false,
+            referenced_by: Vec::new(),
+            references: None,
+            is_primary_key: false,
+            is_unique: false,
+            default: None,
+            is_identity: false,
+            identity_generation: None,
+            is_generated: false,
+            generation_expression: None,
+            comment: None,
+            ..column
+        })
+        .collect()
+}
+
+fn alter_table(
+    database: &mut Database,
+    domains: &[Domain],
+    schema: &str,
+    name: &str,
+    operation: AlterTableOperation,
+) {
+    let Database { tables, enums } = database;
+    let Some(table) = find_table_mut(tables, schema, name) else {
+        return;
+    };
+
+    match operation {
+        AlterTableOperation::AddColumn { column_def, .. }
+            if !table
+                .columns
+                .iter()
+                .any(|c| c.name == ident(&column_def.name)) =>
+        {
+            add_column(table, enums, domains, &column_def);
+        }
+        AlterTableOperation::AddConstraint(constraint) => add_constraint(table, &constraint),
+        AlterTableOperation::DropColumn { column_name, .. } => {
+            let column = ident(&column_name);
+            table.columns.retain(|c| c.name != column);
+            table
+                .foreign_keys
+                .retain(|fk| !fk.columns.iter().any(|c| c.column == column));
+            table
+                .unique_constraints
+                .retain(|u| !u.columns.contains(&column));
+            table
+                .indexes
+                .retain(|i| !i.keys.contains(&IndexKey::Column(column.clone())));
+            if table.primary_key.contains(&column) {
+                table.primary_key.clear();
+            }
+        }
+        AlterTableOperation::DropConstraint { name, .. } => {
+            let name = ident(&name);
+            table.foreign_keys.retain(|fk| fk.name != name);
+            table.unique_constraints.retain(|u| u.name != name);
+            if table.indexes.iter().any(|i| i.name == name && i.is_primary) {
+                table.primary_key.clear();
+            }
+            table.indexes.retain(|i| i.name != name);
+        }
+        AlterTableOperation::DropPrimaryKey => {
+            table.primary_key.clear();
+            table.indexes.retain(|i| !i.is_primary);
+        }
+        AlterTableOperation::RenameConstraint { old_name, new_name } => {
+            let (old_name, new_name) = (ident(&old_name), ident(&new_name));
+            for foreign_key in table.foreign_keys.iter_mut().filter(|f| f.name == old_name) {
+                foreign_key.name = new_name.clone();
+            }
+            for unique in table
+                .unique_constraints
+                .iter_mut()
+                .filter(|u| u.name == old_name)
+            {
+                unique.name = new_name.clone();
+            }
+            for index in table.indexes.iter_mut().filter(|i| i.name == old_name) {
+                index.name = new_name.clone();
+            }
+        }
+        AlterTableOperation::RenameColumn {
+            old_column_name,
+            new_column_name,
+        } => {
+            let (old_name, new_name) = (ident(&old_column_name), ident(&new_column_name));
+            let rename = |column: &mut String| {
+                if *column == old_name {
+                    *column = new_name.clone();
+                }
+            };
+
+            table.columns.iter_mut().for_each(|c| rename(&mut c.name));
+            table.primary_key.iter_mut().for_each(rename);
+            for foreign_key in &mut table.foreign_keys {
+                foreign_key
+                    .columns
+                    .iter_mut()
+                    .for_each(|c| rename(&mut c.column));
+            }
+            for unique in &mut table.unique_constraints {
+                unique.columns.iter_mut().for_each(rename);
+            }
+            for key in table.indexes.iter_mut().flat_map(|i| i.keys.iter_mut()) {
+                if let IndexKey::Column(column) = key {
+                    rename(column);
+                }
+            }
+
+            for foreign_key in tables.iter_mut().flat_map(|t| t.foreign_keys.iter_mut()) {
+                if foreign_key.referenced_schema == schema && foreign_key.referenced_table == name {
+                    foreign_key
+                        .columns
+                        .iter_mut()
+                        .for_each(|c| rename(&mut c.referenced_column));
+                }
+            }
+        }
+        AlterTableOperation::RenameTable { table_name } => {
+            let new_name = qualified(&table_name).1;
+            table.name = new_name.clone();
+
+            for foreign_key in tables.iter_mut().flat_map(|t| t.foreign_keys.iter_mut()) {
+                if foreign_key.referenced_schema == schema && foreign_key.referenced_table == name {
+                    foreign_key.referenced_table = new_name.clone();
+                }
+            }
+        }
+        AlterTableOperation::AlterColumn { column_name, op } => {
+            let schema = table.schema.clone();
+            let Some(column) = table
+                .columns
+                .iter_mut()
+                .find(|c| c.name == ident(&column_name))
+            else {
+                return;
+            };
+
+            match op {
+                AlterColumnOperation::SetNotNull => column.required = true,
+                AlterColumnOperation::DropNotNull => column.required = false,
+                AlterColumnOperation::SetDefault { value } => {
+                    column.default = Some(value.to_string())
+                }
+                AlterColumnOperation::DropDefault => column.default = None,
+                AlterColumnOperation::SetDataType { data_type, .. } => {
+                    let column_type = column_type(&data_type, &schema, enums, domains);
+                    column.kind = column_type.kind;
+                    column.data_type = column_type.data_type;
+                    column.enum_name = column_type.enumeration.map(|e| e.name.clone());
+                    column.enum_schema = column_type.enumeration.map(|e| e.schema.clone());
+                }
+                AlterColumnOperation::AddGenerated { generated_as, .. } => {
+                    column.is_identity = true;
+                    column.required = true;
+                    column.identity_generation = match generated_as {
+                        Some(ast::GeneratedAs::ByDefault) => Some(IdentityGeneration::ByDefault),
+                        _ => Some(IdentityGeneration::Always),
+                    };
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn add_column(table: &mut Table, enums: &[Enum], domains: &[Domain], definition: &ColumnDef) {
+    let name = ident(&definition.name);
+    let ColumnType {
+        kind,
+        data_type,
+        enumeration,
+        is_serial,
+        is_not_null,
+    } = column_type(&definition.data_type, &table.schema, enums, domains);
+
+    let mut column = Column {
+        name,
+        kind,
+        required: is_serial || is_not_null,
+        referenced_by: Vec::new(),
+        references: None,
+        is_primary_key: false,
+        is_unique: false,
+        // A serial column is an integer column defaulting to its own sequence, which PostgreSQL
+        // qualifies when it's outside the default schema.
+        default: is_serial.then(|| {
+            let schema = match table.schema.as_str() {
+                DEFAULT_SCHEMA => String::new(),
+                schema => format!("{schema}."),
+            };
+            format!(
+                "nextval('{schema}{}_{}_seq'::regclass)",
+                table.name,
+                ident(&definition.name)
+            )
+        }),
+        is_identity: false,
+        identity_generation: None,
+        is_generated: false,
+        generation_expression: None,
+        enum_name: enumeration.map(|e| e.name.clone()),
+        enum_schema: enumeration.map(|e| e.schema.clone()),
+        comment: None,
+        data_type,
+    };
+
+    for option in &definition.options {
+        let constraint = option.name.as_ref().map(ident);
+
+        match &option.option {
+            ColumnOption::Null => column.required = false,
+            ColumnOption::NotNull => column.required = true,
+            ColumnOption::Default(expression) => column.default = Some(expression.to_string()),
+            ColumnOption::Comment(comment) => column.comment = Some(comment.clone()),
+            ColumnOption::Unique { is_primary, .. } => add_constraint(
+                table,
+                &match is_primary {
+                    true => TableConstraint::PrimaryKey {
+                        name: option.name.clone(),
+                        index_name: None,
+                        index_type: None,
+                        columns: vec![definition.name.clone()],
+                        index_options: Vec::new(),
+                        characteristics: None,
+                    },
+                    false => TableConstraint::Unique {
+                        name: option.name.clone(),
+                        index_name: None,
+                        index_type_display: ast::KeyOrIndexDisplay::None,
+                        index_type: None,
+                        columns: vec![definition.name.clone()],
+                        index_options: Vec::new(),
+                        characteristics: None,
+                        nulls_distinct: ast::NullsDistinctOption::None,
+                    },
+                },
+            ),
+            ColumnOption::ForeignKey {
+                foreign_table,
+                referred_columns,
+                on_delete,
+                on_update,
+                characteristics,
+            } => {
+                let (referenced_schema, referenced_table) = qualified(foreign_table);
+                let (deferrable, initially_deferred) = deferrability(characteristics);
+
+                table.foreign_keys.push(ForeignKey {
+                    name: constraint.unwrap_or(format!("{}_{}_fkey", table.name, column.name)),
+                    referenced_schema,
+                    referenced_table,
+                    columns: vec![ForeignKeyColumn {
+                        column: column.name.clone(),
+                        // Resolved to the primary key of the referenced table at the end.
+                        referenced_column: referred_columns.first().map(ident).unwrap_or_default(),
+                    }],
+                    on_delete: referential_action(on_delete),
+                    on_update: referential_action(on_update),
+                    deferrable,
+                    initially_deferred,
+                });
+            }
+            ColumnOption::Generated {
+                generated_as,
+                generation_expr,
+                ..
+            } => match generation_expr {
+                Some(expression) => {
+                    column.is_generated = true;
+                    column.generation_expression = Some(expression.to_string());
+                }
+                None => {
+                    column.is_identity = true;
+                    column.required = true;
+                    column.identity_generation = match generated_as {
+                        ast::GeneratedAs::ByDefault => Some(IdentityGeneration::ByDefault),
+                        _ => Some(IdentityGeneration::Always),
+                    };
+                }
+            },
+            _ => {}
+        }
+    }
+
+    // A column level primary key was added before the column itself.
+    if table.primary_key.contains(&column.name) {
+        column.required = true;
+    }
+
+    table.columns.push(column);
+}
+
+/// Add a table constraint, along with the index backing a primary key or unique constraint,
+/// named the way PostgreSQL names them by default.
+fn add_constraint(table: &mut Table, constraint: &TableConstraint) {
+    match constraint {
+        TableConstraint::PrimaryKey { name, columns, .. } => {
+            let columns: Vec<String> = columns.iter().map(ident).collect();
+            for column in table
+                .columns
+                .iter_mut()
+                .filter(|c| columns.contains(&c.name))
+            {
+                column.required = true;
+            }
+
+            table.indexes.retain(|i| !i.is_primary);
+            table.indexes.push(Index {
+                name: name.as_ref().map_or(format!("{}_pkey", table.name), ident),
+                keys: columns.iter().cloned().map(IndexKey::Column).collect(),
+                is_unique: true,
+                is_primary: true,
+                method: "btree".to_string(),
+                predicate: None,
+            });
+            table.primary_key = columns;
+        }
+        TableConstraint::Unique { name, columns, .. } => {
+            let columns: Vec<String> = columns.iter().map(ident).collect();
+            let name = name
+                .as_ref()
+                .map_or(format!("{}_{}_key", table.name, columns.join("_")), ident);
+
+            table.indexes.push(Index {
+                name: name.clone(),
+                keys: columns.iter().cloned().map(IndexKey::Column).collect(),
+                is_unique: true,
+                is_primary: false,
+                method: "btree".to_string(),
+                predicate: None,
+            });
+            table
+                .unique_constraints
+                .push(UniqueConstraint { name, columns });
+        }
+        TableConstraint::ForeignKey {
+            name,
+            columns,
+            foreign_table,
+            referred_columns,
+            on_delete,
+            on_update,
+            characteristics,
+        } => {
+            let columns: Vec<String> = columns.iter().map(ident).collect();
+            let (referenced_schema, referenced_table) = qualified(foreign_table);
+            let (deferrable, initially_deferred) = deferrability(characteristics);
+
+            table.foreign_keys.push(ForeignKey {
+                name: name
+                    .as_ref()
+                    .map_or(format!("{}_{}_fkey", table.name, columns.join("_")), ident),
+                referenced_schema,
+                referenced_table,
+                columns: columns
+                    .iter()
+                    .enumerate()
+                    .map(|(position, column)| ForeignKeyColumn {
+                        column: column.clone(),
+                        referenced_column: referred_columns
+                            .get(position)
+                            .map(ident)
+                            .unwrap_or_default(),
+                    })
+                    .collect(),
+                on_delete: referential_action(on_delete),
+                on_update: referential_action(on_update),
+                deferrable,
+                initially_deferred,
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Keep the tables and enums selected by the options, and derive the per-column key flags and
+/// references, as the live backends do.
+fn finish(database: Database, options: &IntrospectOptions) -> Database {
+    let Database { mut tables, enums } = database;
+
+    // A foreign key without referenced columns references the primary key.
+    let primary_keys: Vec<(String, String, Vec<String>)> = tables
+        .iter()
+        .map(|t| (t.schema.clone(), t.name.clone(), t.primary_key.clone()))
+        .collect();
+    for foreign_key in tables.iter_mut().flat_map(|t| t.foreign_keys.iter_mut()) {
+        let Some((_, _, primary_key)) = primary_keys.iter().find(|(schema, name, _)| {
+            *schema == foreign_key.referenced_schema && *name == foreign_key.referenced_table
+        }) else {
+            continue;
+        };
+
+        for (position, column) in foreign_key.columns.iter_mut().enumerate() {
+            if column.referenced_column.is_empty() {
+                column.referenced_column = primary_key.get(position).cloned().unwrap_or_default();
+            }
+        }
+    }
+
+    let mut keys = Vec::new();
+    let mut indexes = Vec::new();
+    for table in &tables {
+        let key = |name: &str, kind, column: &str, referenced| KeyColumn {
+            name: name.to_string(),
+            kind,
+            schema: table.schema.clone(),
+            table: table.name.clone(),
+            column: column.to_string(),
+            referenced,
+            on_delete: ReferentialAction::NoAction,
+            on_update: ReferentialAction::NoAction,
+            deferrable: false,
+            initially_deferred: false,
+        };
+
+        for column in &table.primary_key {
+            keys.push(key(
+                &format!("{}_pkey", table.name),
+                ConstraintKind::PrimaryKey,
+                column,
+                None,
+            ));
+        }
+        for unique in &table.unique_constraints {
+            for column in &unique.columns {
+                keys.push(key(&unique.name, ConstraintKind::Unique, column, None));
+            }
+        }
+        for foreign_key in &table.foreign_keys {
+            for column in &foreign_key.columns {
+                keys.push(KeyColumn {
+                    on_delete: foreign_key.on_delete,
+                    on_update: foreign_key.on_update,
+                    deferrable: foreign_key.deferrable,
+                    initially_deferred: foreign_key.initially_deferred,
+                    ..key(
+                        &foreign_key.name,
+                        ConstraintKind::ForeignKey,
+                        &column.column,
+                        Some(Reference {
+                            schema: foreign_key.referenced_schema.clone(),
+                            table: foreign_key.referenced_table.clone(),
+                            column: column.referenced_column.clone(),
+                        }),
+                    )
+                });
+            }
+        }
+        for index in &table.indexes {
+            indexes.push(TableIndex {
+                schema: table.schema.clone(),
+                table: table.name.clone(),
+                index: index.clone(),
+            });
+        }
+    }
+
+    let schemas = match options.schemas.is_empty() {
+        true => vec![DEFAULT_SCHEMA.to_string()],
+        false => options.schemas.clone(),
+    };
+    tables.retain(|t| {
+        schemas.contains(&t.schema)
+            && !options.exclude_tables.contains(&t.name)
+            && !options
+                .exclude_tables
+                .contains(&format!("{}.{}", t.schema, t.name))
+    });
+    catalog::assemble(&mut tables, &keys, &indexes);
+
+    // Enums in the introspected schemas, plus those living elsewhere but used by a column.
+    let enums = enums
+        .into_iter()
+        .filter(|e| {
+            schemas.contains(&e.schema)
+                || tables.iter().flat_map(|t| &t.columns).any(|c| {
+                    c.enum_name.as_ref() == Some(&e.name)
+                        && c.enum_schema.as_ref() == Some(&e.schema)
+                })
+        })
+        .collect();
+
+    Database { tables, enums }
+}
+
+/// A column type as the snapshot records it.
+struct ColumnType<'a> {
+    /// The `information_schema` style kind, e.g. `integer` or `USER-DEFINED`.
+    kind: String,
+    data_type: DataType,
+    enumeration: Option<&'a Enum>,
+    is_serial: bool,
+    /// Whether the type is a domain with a `NOT NULL` constraint.
+    is_not_null: bool,
+}
+
+/// Resolve a column type through arrays and domains down to its base type, which may be an enum.
+fn column_type<'a>(
+    data_type: &ast::DataType,
+    schema: &str,
+    enums: &'a [Enum],
+    domains: &[Domain],
+) -> ColumnType<'a> {
+    let mut element = data_type;
+    let mut dimensions = 0;
+    let mut chain: Vec<&Domain> = Vec::new();
+    loop {
+        while let ast::DataType::Array(
+            ArrayElemTypeDef::AngleBracket(inner)
+            | ArrayElemTypeDef::SquareBracket(inner, _)
+            | ArrayElemTypeDef::Parenthesis(inner),
+        ) = element
+        {
+            element = inner;
+            dimensions += 1;
+        }
+
+        let domain = match element {
+            ast::DataType::Custom(type_name, _) => {
+                find_type(domains, type_name, schema, |d| (&d.schema, &d.name))
+            }
+            _ => None,
+        };
+        match domain {
+            Some(domain) if !chain.iter().any(|d| std::ptr::eq(*d, domain)) => {
+                chain.push(domain);
+                element = &domain.data_type;
+            }
+            _ => break,
+        }
+    }
+
+    let (mut name, length, precision, scale) = base_type(element);
+
+    let serial = match name.as_str() {
+        "smallserial" | "serial2" => Some("int2"),
+        "serial" | "serial4" => Some("int4"),
+        "bigserial" | "serial8" => Some("int8"),
+        _ => None,
+    };
+    if let Some(integer) = serial {
+        name = integer.to_string();
+    }
+    let (precision, scale) = match (precision, name.as_str()) {
+        (None, "int2") => (Some(16), Some(0)),
+        (None, "int4") => (Some(32), Some(0)),
+        (None, "int8") => (Some(64), Some(0)),
+        (None, "float4") => (Some(24), None),
+        (None, "float8") => (Some(53), None),
+        _ => (precision, scale),
+    };
+
+    let enumeration = match element {
+        ast::DataType::Custom(type_name, _) => {
+            find_type(enums, type_name, schema, |e| (&e.schema, &e.name))
+        }
+        _ => None,
+    };
+
+    // A domain reports the kind of the type it wraps, like the enum or domain a column uses.
+    let is_array = matches!(data_type, ast::DataType::Array(_))
+        || chain
+            .first()
+            .is_some_and(|d| matches!(d.data_type, ast::DataType::Array(_)));
+    let kind = match (is_array, chain.len(), enumeration) {
+        (true, ..) => "ARRAY".to_string(),
+        (false, 0 | 1, None) => information_schema_name(&name).to_string(),
+        _ => "USER-DEFINED".to_string(),
+    };
+
+    let data_type = DataType {
+        udt_name: match dimensions {
+            0 => name.clone(),
+            _ => format!("_{name}"),
+        },
+        name,
+        dimensions,
+        length,
+        precision,
+        scale,
+        domains: chain.iter().map(|d| d.name.clone()).collect(),
+    };
+
+    ColumnType {
+        kind,
+        data_type,
+        enumeration,
+        is_serial: serial.is_some(),
+        is_not_null: chain.first().is_some_and(|d| d.is_not_null),
+    }
+}
+
+/// Find an enum or domain by its name as a column type uses it, which is either qualified or
+/// resolved against the schema of the table, and then the default schema.
+fn find_type<'a, T>(
+    types: &'a [T],
+    type_name: &ObjectName,
+    schema: &str,
+    key: impl Fn(&T) -> (&str, &str),
+) -> Option<&'a T> {
+    let (type_schema, type_name) = match type_name.0.len() {
+        1 => (schema.to_string(), ident(&type_name.0[0])),
+        _ => qualified(type_name),
+    };
+
+    types
+        .iter()
+        .find(|t| key(t) == (&type_schema, &type_name))
+        .or_else(|| {
+            types
+                .iter()
+                .find(|t| key(t) == (DEFAULT_SCHEMA, &type_name))
+        })
+}
+
+/// The PostgreSQL type name of a parsed type, e.g. `int4` for `INTEGER`, with its length,
+/// precision and scale.
+fn base_type(data_type: &ast::DataType) -> (String, Option<i32>, Option<i32>, Option<i32>) {
+    use ast::DataType as T;
+
+    let character_length = |length: &Option<CharacterLength>| match length {
+        Some(CharacterLength::IntegerLength { length, .. }) => Some(*length as i32),
+        _ => None,
+    };
+    let name = |name: &str| (name.to_string(), None, None, None);
+
+    match data_type {
+        T::Character(length) | T::Char(length) => (
+            "bpchar".to_string(),
+            character_length(length).or(Some(1)),
+            None,
+            None,
+        ),
+        T::CharacterVarying(length)
+        | T::CharVarying(length)
+        | T::Varchar(length)
+        | T::Nvarchar(length) => ("varchar".to_string(), character_length(length), None, None),
+        T::Numeric(info) | T::Decimal(info) | T::Dec(info) => match info {
+            ExactNumberInfo::None => name("numeric"),
+            ExactNumberInfo::Precision(precision) => (
+                "numeric".to_string(),
+                None,
+                Some(*precision as i32),
+                Some(0),
+            ),
+            ExactNumberInfo::PrecisionAndScale(precision, scale) => (
+                "numeric".to_string(),
+                None,
+                Some(*precision as i32),
+                Some(*scale as i32),
+            ),
+        },
+        T::Float(Some(precision)) if *precision <= 24 => name("float4"),
+        T::Float(_) | T::Float8 | T::Double | T::DoublePrecision => name("float8"),
+        T::Float4 | T::Real => name("float4"),
+        T::Int2(_) | T::SmallInt(_) => name("int2"),
+        T::Int(_) | T::Int4(_) | T::Integer(_) => name("int4"),
+        T::Int8(_) | T::BigInt(_) => name("int8"),
+        T::Bool | T::Boolean => name("bool"),
+        T::Time(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => name("timetz"),
+        T::Time(..) => name("time"),
+        T::Timestamp(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => name("timestamptz"),
+        T::Timestamp(..) => name("timestamp"),
+        T::Bit(length) => ("bit".to_string(), length.map(|l| l as i32), None, None),
+        T::BitVarying(length) => ("varbit".to_string(), length.map(|l| l as i32), None, None),
+        T::Uuid => name("uuid"),
+        T::Date => name("date"),
+        T::Interval => name("interval"),
+        T::JSON => name("json"),
+        T::JSONB => name("jsonb"),
+        T::Regclass => name("regclass"),
+        T::Text => name("text"),
+        T::Bytea => name("bytea"),
+        T::Custom(type_name, _) => name(&qualified(type_name).1),
+        other => name(&other.to_string().to_lowercase()),
+    }
+}
+
+/// The name `information_schema.columns.data_type` reports for a built-in type.
+fn information_schema_name(name: &str) -> &str {
+    match name {
+        "int2" => "smallint",
+        "int4" => "integer",
+        "int8" => "bigint",
+        "float4" => "real",
+        "float8" => "double precision",
+        "bool" => "boolean",
+        "bpchar" => "character",
+        "varchar" => "character varying",
+        "varbit" => "bit varying",
+        "time" => "time without time zone",
+        "timetz" => "time with time zone",
+        "timestamp" => "timestamp without time zone",
+        "timestamptz" => "timestamp with time zone",
+        name => name,
+    }
+}
+
+fn empty_table(schema: String, name: String, kind: TableKind) -> Table {
+    Table {
+        schema,
+        name,
+        kind,
+        definition: None,
+        comment: None,
+        columns: Vec::new(),
+        primary_key: Vec::new(),
+        foreign_keys: Vec::new(),
+        unique_constraints: Vec::new(),
+        indexes: Vec::new(),
+    }
+}
+
+fn find_table<'a>(tables: &'a [Table], schema: &str, name: &str) -> Option<&'a Table> {
+    tables.iter().find(|t| t.schema == schema && t.name == name)
+}
+
+fn find_table_mut<'a>(tables: &'a mut [Table], schema: &str, name: &str) -> Option<&'a mut Table> {
+    tables
+        .iter_mut()
+        .find(|t| t.schema == schema && t.name == name)
+}
+
+/// Unquoted identifiers are folded to lowercase, as PostgreSQL does.
+fn ident(ident: &Ident) -> String {
+    match ident.quote_style {
+        Some(_) => ident.value.clone(),
+        None => ident.value.to_lowercase(),
+    }
+}
+
+/// The schema and name of a possibly qualified name, e.g. `billing.accounts`.
+fn qualified(name: &ObjectName) -> (String, String) {
+    let mut parts: Vec<String> = name.0.iter().map(ident).collect();
+    let name = parts.pop().unwrap_or_default();
+    let schema = parts.pop().unwrap_or(DEFAULT_SCHEMA.to_string());
+
+    (schema, name)
+}
+
+fn deferrability(characteristics: &Option<ConstraintCharacteristics>) -> (bool, bool) {
+    let Some(characteristics) = characteristics else {
+        return (false, false);
+    };
+
+    let initially_deferred = characteristics.initially == Some(DeferrableInitial::Deferred);
+    (
+        characteristics.deferrable.unwrap_or(false) || initially_deferred,
+        initially_deferred,
+    )
+}
+
+fn referential_action(action: &Option<ast::ReferentialAction>) -> ReferentialAction {
+    match action {
+        Some(ast::ReferentialAction::Restrict) => ReferentialAction::Restrict,
+        Some(ast::ReferentialAction::Cascade) => ReferentialAction::Cascade,
+        Some(ast::ReferentialAction::SetNull) => ReferentialAction::SetNull,
+        Some(ast::ReferentialAction::SetDefault) => ReferentialAction::SetDefault,
+        Some(ast::ReferentialAction::NoAction) | None => ReferentialAction::NoAction,
+    }
+}
@@ -0,0 +1,469 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::database::{
+    Column, Database, Enum, ForeignKey, Index, Table, TableKind, UniqueConstraint,
+};
+
+/// The structural differences between two snapshots. Enums come before tables, and removals
+/// before additions.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+}
+
+/// A single difference between two snapshots.
+///
+/// A constraint or index that changed under the same name is reported as removed and added
+/// again, which is also how it has to be migrated.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    EnumAdded {
+        #[serde(rename = "enum")]
+        enumeration: Enum,
+    },
+    EnumRemoved {
+        #[serde(rename = "enum")]
+        enumeration: Enum,
+    },
+    EnumLabelsChanged {
+        schema: String,
+        name: String,
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    TableAdded {
+        table: Table,
+    },
+    TableRemoved {
+        table: Table,
+    },
+    ColumnAdded {
+        schema: String,
+        table: String,
+        column: Column,
+    },
+    ColumnRemoved {
+        schema: String,
+        table: String,
+        column: Column,
+    },
+    ColumnTypeChanged {
+        schema: String,
+        table: String,
+        column: String,
+        from: String,
+        to: String,
+    },
+    ColumnNullabilityChanged {
+        schema: String,
+        table: String,
+        column: String,
+        required: bool,
+    },
+    ColumnDefaultChanged {
+        schema: String,
+        table: String,
+        column: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    PrimaryKeyChanged {
+        schema: String,
+        table: String,
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    ForeignKeyAdded {
+        schema: String,
+        table: String,
+        foreign_key: ForeignKey,
+    },
+    ForeignKeyRemoved {
+        schema: String,
+        table: String,
+        foreign_key: ForeignKey,
+    },
+    UniqueConstraintAdded {
+        schema: String,
+        table: String,
+        unique_constraint: UniqueConstraint,
+    },
+    UniqueConstraintRemoved {
+        schema: String,
+        table: String,
+        unique_constraint: UniqueConstraint,
+    },
+    IndexAdded {
+        schema: String,
+        table: String,
+        index: Index,
+    },
+    IndexRemoved {
+        schema: String,
+        table: String,
+        index: Index,
+    },
+}
+
+impl Diff {
+    /// Compare an older snapshot with a newer one.
+    ///
+    /// Tables and enums are matched by schema and name, and columns, constraints and indexes by
+    /// name, so a rename shows up as a removal and an addition.
+    pub fn new(old: &Database, new: &Database) -> Self {
+        let mut changes = Vec::new();
+
+        for enumeration in &old.enums {
+            if find_enum(&new.enums, enumeration).is_none() {
+                changes.push(Change::EnumRemoved {
+                    enumeration: enumeration.clone(),
+                });
+            }
+        }
+        for enumeration in &new.enums {
+            match find_enum(&old.enums, enumeration) {
+                None => changes.push(Change::EnumAdded {
+                    enumeration: enumeration.clone(),
+                }),
+                Some(old) if old.labels != enumeration.labels => {
+                    changes.push(Change::EnumLabelsChanged {
+                        schema: enumeration.schema.clone(),
+                        name: enumeration.name.clone(),
+                        from: old.labels.clone(),
+                        to: enumeration.labels.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        for table in &old.tables {
+            if find_table(&new.tables, table).is_none() {
+                changes.push(Change::TableRemoved {
+                    table: table.clone(),
+                });
+            }
+        }
+        for table in &new.tables {
+            match find_table(&old.tables, table) {
+                None => changes.push(Change::TableAdded {
+                    table: table.clone(),
+                }),
+                Some(old) => diff_table(&mut changes, old, table),
+            }
+        }
+
+        Self { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+fn diff_table(changes: &mut Vec<Change>, old: &Table, new: &Table) {
+    let (schema, table) = (new.schema.clone(), new.name.clone());
+
+    for column in &old.columns {
+        if !new.columns.iter().any(|c| c.name == column.name) {
+            changes.push(Change::ColumnRemoved {
+                schema: schema.clone(),
+                table: table.clone(),
+                column: column.clone(),
+            });
+        }
+    }
+    for column in &new.columns {
+        let Some(old) = old.columns.iter().find(|c| c.name == column.name) else {
+            changes.push(Change::ColumnAdded {
+                schema: schema.clone(),
+                table: table.clone(),
+                column: column.clone(),
+            });
+            continue;
+        };
+
+        // Snapshots pulled before enum schemas were recorded don't know them, so the schema
+        // only counts when both sides have one.
+        let enum_moved = match (&old.enum_schema, &column.enum_schema) {
+            (Some(from), Some(to)) => from != to,
+            _ => false,
+        };
+        if old.sql_type() != column.sql_type() || enum_moved {
+            changes.push(Change::ColumnTypeChanged {
+                schema: schema.clone(),
+                table: table.clone(),
+                column: column.name.clone(),
+                from: old.qualified_sql_type(),
+                to: column.qualified_sql_type(),
+            });
+        }
+        if old.required != column.required {
+            changes.push(Change::ColumnNullabilityChanged {
+                schema: schema.clone(),
+                table: table.clone(),
+                column: column.name.clone(),
+                required: column.required,
+            });
+        }
+        if old.default != column.default {
+            changes.push(Change::ColumnDefaultChanged {
+                schema: schema.clone(),
+                table: table.clone(),
+                column: column.name.clone(),
+                from: old.default.clone(),
+                to: column.default.clone(),
+            });
+        }
+    }
+
+    if old.primary_key != new.primary_key {
+        changes.push(Change::PrimaryKeyChanged {
+            schema: schema.clone(),
+            table: table.clone(),
+            from: old.primary_key.clone(),
+            to: new.primary_key.clone(),
+        });
+    }
+
+    for foreign_key in removed(&old.foreign_keys, &new.foreign_keys) {
+        changes.push(Change::ForeignKeyRemoved {
+            schema: schema.clone(),
+            table: table.clone(),
+            foreign_key,
+        });
+    }
+    for foreign_key in removed(&new.foreign_keys, &old.foreign_keys) {
+        changes.push(Change::ForeignKeyAdded {
+            schema: schema.clone(),
+            table: table.clone(),
+            foreign_key,
+        });
+    }
+
+    for unique_constraint in removed(&old.unique_constraints, &new.unique_constraints) {
+        changes.push(Change::UniqueConstraintRemoved {
+            schema: schema.clone(),
+            table: table.clone(),
+            unique_constraint,
+        });
+    }
+    for unique_constraint in removed(&new.unique_constraints, &old.unique_constraints) {
+        changes.push(Change::UniqueConstraintAdded {
+            schema: schema.clone(),
+            table: table.clone(),
+            unique_constraint,
+        });
+    }
+
+    for index in removed(&old.indexes, &new.indexes) {
+        changes.push(Change::IndexRemoved {
+            schema: schema.clone(),
+            table: table.clone(),
+            index,
+        });
+    }
+    for index in removed(&new.indexes, &old.indexes) {
+        changes.push(Change::IndexAdded {
+            schema: schema.clone(),
+            table: table.clone(),
+            index,
+        });
+    }
+}
+
+/// The items of `from` that don't appear unchanged in `to`.
+fn removed<T: PartialEq + Clone>(from: &[T], to: &[T]) -> Vec<T> {
+    from.iter().filter(|i| !to.contains(i)).cloned().collect()
+}
+
+fn find_table<'a>(tables: &'a [Table], table: &Table) -> Option<&'a Table> {
+    tables
+        .iter()
+        .find(|t| t.schema == table.schema && t.name == table.name)
+}
+
+fn find_enum<'a>(enums: &'a [Enum], enumeration: &Enum) -> Option<&'a Enum> {
+    enums
+        .iter()
+        .find(|e| e.schema == enumeration.schema && e.name == enumeration.name)
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// One line per change, prefixed with `+` for additions, `-` for removals and `~` for changes.
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::EnumAdded { enumeration } => write!(
+                f,
+                "+ enum {}.{} ({})",
+                enumeration.schema,
+                enumeration.name,
+                enumeration.labels.join(", ")
+            ),
+            Change::EnumRemoved { enumeration } => {
+                write!(f, "- enum {}.{}", enumeration.schema, enumeration.name)
+            }
+            Change::EnumLabelsChanged {
+                schema,
+                name,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ enum {schema}.{name} labels ({}) -> ({})",
+                from.join(", "),
+                to.join(", ")
+            ),
+            Change::TableAdded { table } => {
+                write!(f, "+ {} {}.{}", kind(table), table.schema, table.name)
+            }
+            Change::TableRemoved { table } => {
+                write!(f, "- {} {}.{}", kind(table), table.schema, table.name)
+            }
+            Change::ColumnAdded {
+                schema,
+                table,
+                column,
+            } => write!(
+                f,
+                "+ column {schema}.{table}.{} {}{}",
+                column.name,
+                column.sql_type(),
+                if column.required { " not null" } else { "" }
+            ),
+            Change::ColumnRemoved {
+                schema,
+                table,
+                column,
+            } => write!(f, "- column {schema}.{table}.{}", column.name),
+            Change::ColumnTypeChanged {
+                schema,
+                table,
+                column,
+                from,
+                to,
+            } => write!(f, "~ column {schema}.{table}.{column} type {from} -> {to}"),
+            Change::ColumnNullabilityChanged {
+                schema,
+                table,
+                column,
+                required,
+            } => write!(
+                f,
+                "~ column {schema}.{table}.{column} {}",
+                match required {
+                    true => "nullable -> not null",
+                    false => "not null -> nullable",
+                }
+            ),
+            Change::ColumnDefaultChanged {
+                schema,
+                table,
+                column,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ column {schema}.{table}.{column} default {} -> {}",
+                from.as_deref().unwrap_or("none"),
+                to.as_deref().unwrap_or("none")
+            ),
+            Change::PrimaryKeyChanged {
+                schema,
+                table,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ primary key {schema}.{table} ({}) -> ({})",
+                from.join(", "),
+                to.join(", ")
+            ),
+            Change::ForeignKeyAdded {
+                schema,
+                table,
+                foreign_key,
+            } => write!(
+                f,
+                "+ foreign key {schema}.{table}.{} ({}) -> {}.{} ({})",
+                foreign_key.name,
+                foreign_key
+                    .columns
+                    .iter()
+                    .map(|c| c.column.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                foreign_key.referenced_schema,
+                foreign_key.referenced_table,
+                foreign_key
+                    .columns
+                    .iter()
+                    .map(|c| c.referenced_column.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Change::ForeignKeyRemoved {
+                schema,
+                table,
+                foreign_key,
+            } => write!(f, "- foreign key {schema}.{table}.{}", foreign_key.name),
+            Change::UniqueConstraintAdded {
+                schema,
+                table,
+                unique_constraint,
+            } => write!(
+                f,
+                "+ unique {schema}.{table}.{} ({})",
+                unique_constraint.name,
+                unique_constraint.columns.join(", ")
+            ),
+            Change::UniqueConstraintRemoved {
+                schema,
+                table,
+                unique_constraint,
+            } => write!(f, "- unique {schema}.{table}.{}", unique_constraint.name),
+            Change::IndexAdded {
+                schema,
+                table,
+                index,
+            } => write!(
+                f,
+                "+ {}index {schema}.{table}.{} ({})",
+                if index.is_unique { "unique " } else { "" },
+                index.name,
+                index
+                    .keys
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Change::IndexRemoved {
+                schema,
+                table,
+                index,
+            } => write!(f, "- index {schema}.{table}.{}", index.name),
+        }
+    }
+}
+
+fn kind(table: &Table) -> &'static str {
+    match table.kind {
+        TableKind::Table => "table",
+        TableKind::View => "view",
+        TableKind::MaterializedView => "materialized view",
+    }
+}
@@ -5,6 +5,7 @@ pub enum Error {
     MissingDatabaseUrl(std::env::VarError),
     Connection(sqlx::Error),
     Query(sqlx::Error),
+    UnsupportedDatabase(String),
 }
 
 impl std::fmt::Display for Error {
@@ -15,6 +16,9 @@ impl std::fmt::Display for Error {
             Error::MissingDatabaseUrl(error) => write!(f, "Missing database URL: {error}"),
             Error::Connection(error) => write!(f, "Connection error: {error}"),
             Error::Query(error) => write!(f, "Query error: {error}"),
+            Error::UnsupportedDatabase(scheme) => {
+                write!(f, "Unsupported database URL scheme: {scheme}")
+            }
         }
     }
 }
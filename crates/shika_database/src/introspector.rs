@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, database::Database, error::Error, mysql, postgres, sqlite};
+
+/// What to introspect, independent of the backend.
+#[derive(Debug, Clone, Default)]
+pub struct IntrospectOptions {
+    /// The schemas to introspect. Without any, the default schema of the backend is used.
+    pub schemas: Vec<String>,
+    /// Tables to leave out, either by their bare name or by their schema-qualified name, e.g.
+    /// `audit.events`.
+    pub exclude_tables: Vec<String>,
+}
+
+/// A source of database snapshots.
+///
+/// Implementations don't have to return the snapshot in canonical order; call
+/// [`Database::sort`] on the result before writing it.
+pub trait Introspector {
+    fn introspect(
+        &self,
+        options: &IntrospectOptions,
+    ) -> impl Future<Output = Result<Database>> + Send;
+}
+
+/// The built-in introspection backends.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl Source {
+    /// Choose the backend from the scheme of a connection URL: `postgres:` (or `postgresql:`),
+    /// `mysql:` (or `mariadb:`) and `sqlite:`.
+    pub fn from_url(database_url: &str) -> Result<Self> {
+        let scheme = database_url
+            .split_once(':')
+            .map(|(scheme, _)| scheme)
+            .unwrap_or_default();
+
+        match scheme {
+            "postgres" | "postgresql" => Ok(Source::Postgres),
+            "mysql" | "mariadb" => Ok(Source::MySql),
+            "sqlite" => Ok(Source::Sqlite),
+            _ => Err(Error::UnsupportedDatabase(scheme.to_string())),
+        }
+    }
+}
+
+/// Introspects PostgreSQL through `pg_catalog`.
+#[derive(Debug, Clone)]
+pub struct PostgresIntrospector {
+    database_url: String,
+}
+
+impl PostgresIntrospector {
+    pub fn new<S: Into<String>>(database_url: S) -> Self {
+        Self {
+            database_url: database_url.into(),
+        }
+    }
+}
+
+impl Introspector for PostgresIntrospector {
+    async fn introspect(&self, options: &IntrospectOptions) -> Result<Database> {
+        postgres::fetch(
+            &self.database_url,
+            options.schemas.clone(),
+            options.exclude_tables.clone(),
+        )
+        .await
+    }
+}
+
+/// Introspects MySQL and MariaDB through `information_schema`.
+#[derive(Debug, Clone)]
+pub struct MySqlIntrospector {
+    database_url: String,
+}
+
+impl MySqlIntrospector {
+    pub fn new<S: Into<String>>(database_url: S) -> Self {
+        Self {
+            database_url: database_url.into(),
+        }
+    }
+}
+
+impl Introspector for MySqlIntrospector {
+    async fn introspect(&self, options: &IntrospectOptions) -> Result<Database> {
+        mysql::fetch(
+            &self.database_url,
+            options.schemas.clone(),
+            options.exclude_tables.clone(),
+        )
+        .await
+    }
+}
+
+/// Introspects a SQLite database file, which is opened read-only.
+#[derive(Debug, Clone)]
+pub struct SqliteIntrospector {
+    database_url: String,
+}
+
+impl SqliteIntrospector {
+    pub fn new<S: Into<String>>(database_url: S) -> Self {
+        Self {
+            database_url: database_url.into(),
+        }
+    }
+}
+
+impl Introspector for SqliteIntrospector {
+    async fn introspect(&self, options: &IntrospectOptions) -> Result<Database> {
+        sqlite::fetch(
+            &self.database_url,
+            options.schemas.clone(),
+            options.exclude_tables.clone(),
+        )
+        .await
+    }
+}
+
+/// A snapshot introspects to itself, which makes it an in-memory fixture. The options are
+/// ignored.
+impl Introspector for Database {
+    async fn introspect(&self, _options: &IntrospectOptions) -> Result<Database> {
+        Ok(self.clone())
+    }
+}
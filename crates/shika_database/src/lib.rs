@@ -1,7 +1,26 @@
+mod catalog;
+mod compat;
 mod database;
+mod ddl;
+mod diff;
 mod error;
+mod introspector;
+mod migration;
+mod mysql;
+mod postgres;
+mod sqlite;
 
 pub(crate) type Result<T> = std::result::Result<T, error::Error>;
 
-pub use database::Database;
+pub use compat::Severity;
+pub use database::{
+    Column, DataType, Database, Enum, ForeignKey, ForeignKeyColumn, IdentityGeneration, Index,
+    IndexKey, Reference, ReferentialAction, Table, TableKind, UniqueConstraint,
+};
+pub use ddl::{SkippedStatement, SqlIntrospector};
+pub use diff::{Change, Diff};
 pub use error::Error;
+pub use introspector::{
+    IntrospectOptions, Introspector, MySqlIntrospector, PostgresIntrospector, Source,
+    SqliteIntrospector,
+};
@@ -0,0 +1,767 @@
+use crate::{
+    database::{
+        Column, Enum, ForeignKey, IdentityGeneration, Index, IndexKey, ReferentialAction, Table,
+        TableKind,
+    },
+    diff::{Change, Diff},
+};
+
+impl Diff {
+    /// The reverse of the diff, which undoes it.
+    pub fn inverse(&self) -> Diff {
+        Diff {
+            changes: self.changes.iter().map(Change::inverse).collect(),
+        }
+    }
+
+    /// PostgreSQL statements that migrate the old snapshot to the new one. The statements are
+    /// PostgreSQL whatever the snapshots were pulled from, so they don't apply to MySQL or
+    /// SQLite databases.
+    ///
+    /// Statements are ordered in phases so that nothing is dropped while something else still
+    /// depends on it, and nothing is created before what it depends on:
+    ///
+    /// 1. create the schemas of new tables and enums, create enums and add their labels,
+    /// 2. drop views, foreign keys, primary keys, unique constraints and indexes,
+    /// 3. drop tables and columns, and alter columns,
+    /// 4. create tables and add columns,
+    /// 5. create views, primary keys, unique constraints, indexes and foreign keys,
+    /// 6. drop enums.
+    ///
+    /// Views are created after the views they select from and dropped before them. Schemas are
+    /// created if they don't exist yet, but never dropped, since they may hold objects the
+    /// snapshots don't cover.
+    ///
+    /// Changes that can't be expressed in SQL, such as removing enum labels, are written as
+    /// comments. New enum labels can't be used in the transaction that adds them, so see
+    /// [`Diff::split_enum_labels`] for running them in a migration of their own.
+    pub fn to_sql(&self) -> String {
+        let mut statements = Vec::new();
+
+        let mut schemas: Vec<&str> = Vec::new();
+        for change in &self.changes {
+            let schema = match change {
+                Change::TableAdded { table } => &table.schema,
+                Change::EnumAdded { enumeration } => &enumeration.schema,
+                _ => continue,
+            };
+            // Every PostgreSQL database has a `public` schema to begin with.
+            if schema != "public" && !schemas.contains(&schema.as_str()) {
+                schemas.push(schema);
+                statements.push(format!("CREATE SCHEMA IF NOT EXISTS {};", quote(schema)));
+            }
+        }
+
+        for change in &self.changes {
+            match change {
+                Change::EnumAdded { enumeration } => statements.push(format!(
+                    "CREATE TYPE {} AS ENUM ({});",
+                    qualified(&enumeration.schema, &enumeration.name),
+                    enum_labels(enumeration)
+                )),
+                Change::EnumLabelsChanged {
+                    schema,
+                    name,
+                    from,
+                    to,
+                } => {
+                    // Every label is placed next to one that exists by the time it's added: the
+                    // ones before the first existing label go before their successor, from the
+                    // last to the first, and the rest after their predecessor.
+                    let first = to.iter().position(|l| from.contains(l)).unwrap_or(0);
+                    let before = (0..first).rev().map(|position| {
+                        (position, format!(" BEFORE {}", literal(&to[position + 1])))
+                    });
+                    let after = (first..to.len()).map(|position| match position {
+                        0 => (position, String::new()),
+                        _ => (position, format!(" AFTER {}", literal(&to[position - 1]))),
+                    });
+                    for (position, placement) in before.chain(after) {
+                        if from.contains(&to[position]) {
+                            continue;
+                        }
+
+                        statements.push(format!(
+                            "ALTER TYPE {} ADD VALUE {}{placement};",
+                            qualified(schema, name),
+                            literal(&to[position]),
+                        ));
+                    }
+                    for label in from.iter().filter(|l| !to.contains(l)) {
+                        statements.push(format!(
+                            "-- The label {} can't be removed from {} without recreating the type.",
+                            literal(label),
+                            qualified(schema, name)
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let removed_views = self.changes.iter().filter_map(|change| match change {
+            Change::TableRemoved { table } if table.kind != TableKind::Table => Some(table),
+            _ => None,
+        });
+        for view in dependency_order(removed_views.collect()).into_iter().rev() {
+            statements.push(drop_table(view));
+        }
+
+        for change in &self.changes {
+            match change {
+                Change::ForeignKeyRemoved {
+                    schema,
+                    table,
+                    foreign_key,
+                } => statements.push(format!(
+                    "ALTER TABLE {} DROP CONSTRAINT {};",
+                    qualified(schema, table),
+                    quote(&foreign_key.name)
+                )),
+                Change::PrimaryKeyChanged {
+                    schema,
+                    table,
+                    from,
+                    ..
+                } if !from.is_empty() => statements.push(format!(
+                    "ALTER TABLE {} DROP CONSTRAINT {};",
+                    qualified(schema, table),
+                    quote(&self.primary_key_name(schema, table, false))
+                )),
+                Change::UniqueConstraintRemoved {
+                    schema,
+                    table,
+                    unique_constraint,
+                } => statements.push(format!(
+                    "ALTER TABLE {} DROP CONSTRAINT {};",
+                    qualified(schema, table),
+                    quote(&unique_constraint.name)
+                )),
+                Change::IndexRemoved {
+                    schema,
+                    table,
+                    index,
+                } if !self.is_constraint_index(schema, table, index) => {
+                    statements.push(format!("DROP INDEX {};", qualified(schema, &index.name)))
+                }
+                _ => {}
+            }
+        }
+
+        for change in &self.changes {
+            match change {
+                Change::TableRemoved { table } if table.kind == TableKind::Table => {
+                    statements.push(drop_table(table))
+                }
+                Change::ColumnRemoved {
+                    schema,
+                    table,
+                    column,
+                } => statements.push(format!(
+                    "ALTER TABLE {} DROP COLUMN {};",
+                    qualified(schema, table),
+                    quote(&column.name)
+                )),
+                Change::ColumnTypeChanged {
+                    schema,
+                    table,
+                    column,
+                    to,
+                    ..
+                } => statements.push(format!(
+                    "ALTER TABLE {} ALTER COLUMN {} TYPE {to} USING {}::{to};",
+                    qualified(schema, table),
+                    quote(column),
+                    quote(column)
+                )),
+                Change::ColumnNullabilityChanged {
+                    schema,
+                    table,
+                    column,
+                    required,
+                } => statements.push(format!(
+                    "ALTER TABLE {} ALTER COLUMN {} {} NOT NULL;",
+                    qualified(schema, table),
+                    quote(column),
+                    if *required { "SET" } else { "DROP" }
+                )),
+                Change::ColumnDefaultChanged {
+                    schema,
+                    table,
+                    column,
+                    to,
+                    ..
+                } => statements.push(format!(
+                    "ALTER TABLE {} ALTER COLUMN {} {};",
+                    qualified(schema, table),
+                    quote(column),
+                    match to {
+                        Some(default) => format!("SET DEFAULT {default}"),
+                        None => "DROP DEFAULT".to_string(),
+                    }
+                )),
+                _ => {}
+            }
+        }
+
+        for change in &self.changes {
+            match change {
+                Change::TableAdded { table } if table.definition.is_none() => {
+                    statements.extend(create_table(table))
+                }
+                Change::ColumnAdded {
+                    schema,
+                    table,
+                    column,
+                } => {
+                    statements.push(format!(
+                        "ALTER TABLE {} ADD COLUMN {};",
+                        qualified(schema, table),
+                        column_definition(column)
+                    ));
+                    statements.extend(column_comment(schema, table, column));
+                }
+                _ => {}
+            }
+        }
+
+        let added_views = self.changes.iter().filter_map(|change| match change {
+            Change::TableAdded { table } if table.definition.is_some() => Some(table),
+            _ => None,
+        });
+        for view in dependency_order(added_views.collect()) {
+            statements.extend(create_table(view));
+        }
+
+        for change in &self.changes {
+            match change {
+                Change::PrimaryKeyChanged {
+                    schema, table, to, ..
+                } if !to.is_empty() => statements.push(format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} PRIMARY KEY ({});",
+                    qualified(schema, table),
+                    quote(&self.primary_key_name(schema, table, true)),
+                    columns(to)
+                )),
+                Change::UniqueConstraintAdded {
+                    schema,
+                    table,
+                    unique_constraint,
+                } => statements.push(format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} UNIQUE ({});",
+                    qualified(schema, table),
+                    quote(&unique_constraint.name),
+                    columns(&unique_constraint.columns)
+                )),
+                Change::IndexAdded {
+                    schema,
+                    table,
+                    index,
+                } if !self.is_constraint_index(schema, table, index) => {
+                    statements.push(create_index(schema, table, index))
+                }
+                _ => {}
+            }
+        }
+
+        // Foreign keys last, once every table and key they reference exists.
+        for change in &self.changes {
+            match change {
+                Change::TableAdded { table } => {
+                    for foreign_key in &table.foreign_keys {
+                        statements.push(add_foreign_key(&table.schema, &table.name, foreign_key));
+                    }
+                }
+                Change::ForeignKeyAdded {
+                    schema,
+                    table,
+                    foreign_key,
+                } => statements.push(add_foreign_key(schema, table, foreign_key)),
+                _ => {}
+            }
+        }
+
+        for change in &self.changes {
+            if let Change::EnumRemoved { enumeration } = change {
+                statements.push(format!(
+                    "DROP TYPE {};",
+                    qualified(&enumeration.schema, &enumeration.name)
+                ));
+            }
+        }
+
+        let mut sql = statements.join("\n");
+        if !sql.is_empty() {
+            sql.push('\n');
+        }
+
+        sql
+    }
+
+    /// Split off the changes to enum labels, which go first in a migration of their own.
+    ///
+    /// PostgreSQL can't use a label added by `ALTER TYPE ... ADD VALUE` until the transaction
+    /// that added it commits, and migration tools run each migration in a transaction. Returns
+    /// the label changes and the rest, either of which may be empty.
+    pub fn split_enum_labels(&self) -> (Diff, Diff) {
+        let (labels, rest) = self
+            .changes
+            .iter()
+            .cloned()
+            .partition(|change| matches!(change, Change::EnumLabelsChanged { .. }));
+
+        (Diff { changes: labels }, Diff { changes: rest })
+    }
+
+    /// The name of the primary key of a table before or after the change, taken from the index
+    /// backing it. Snapshots without indexes fall back to the PostgreSQL default name.
+    fn primary_key_name(&self, schema: &str, table: &str, is_added: bool) -> String {
+        self.changes
+            .iter()
+            .find_map(|change| match change {
+                Change::IndexAdded {
+                    schema: s,
+                    table: t,
+                    index,
+                } if is_added => (s == schema && t == table && index.is_primary).then_some(index),
+                Change::IndexRemoved {
+                    schema: s,
+                    table: t,
+                    index,
+                } if !is_added => (s == schema && t == table && index.is_primary).then_some(index),
+                _ => None,
+            })
+            .map_or(format!("{table}_pkey"), |index| index.name.clone())
+    }
+
+    /// Whether an index backs a primary key or unique constraint, and is created and dropped
+    /// along with it.
+    fn is_constraint_index(&self, schema: &str, table: &str, index: &Index) -> bool {
+        index.is_primary
+            || self.changes.iter().any(|change| match change {
+                Change::UniqueConstraintAdded {
+                    schema: s,
+                    table: t,
+                    unique_constraint,
+                }
+                | Change::UniqueConstraintRemoved {
+                    schema: s,
+                    table: t,
+                    unique_constraint,
+                } => s == schema && t == table && unique_constraint.name == index.name,
+                _ => false,
+            })
+    }
+}
+
+impl Change {
+    /// The change that undoes this one.
+    pub fn inverse(&self) -> Change {
+        match self.clone() {
+            Change::EnumAdded { enumeration } => Change::EnumRemoved { enumeration },
+            Change::EnumRemoved { enumeration } => Change::EnumAdded { enumeration },
+            Change::EnumLabelsChanged {
+                schema,
+                name,
+                from,
+                to,
+            } => Change::EnumLabelsChanged {
+                schema,
+                name,
+                from: to,
+                to: from,
+            },
+            Change::TableAdded { table } => Change::TableRemoved { table },
+            Change::TableRemoved { table } => Change::TableAdded { table },
+            Change::ColumnAdded {
+                schema,
+                table,
+                column,
+            } => Change::ColumnRemoved {
+                schema,
+                table,
+                column,
+            },
+            Change::ColumnRemoved {
+                schema,
+                table,
+                column,
+            } => Change::ColumnAdded {
+                schema,
+                table,
+                column,
+            },
+            Change::ColumnTypeChanged {
+                schema,
+                table,
+                column,
+                from,
+                to,
+            } => Change::ColumnTypeChanged {
+                schema,
+                table,
+                column,
+                from: to,
+                to: from,
+            },
+            Change::ColumnNullabilityChanged {
+                schema,
+                table,
+                column,
+                required,
+            } => Change::ColumnNullabilityChanged {
+                schema,
+                table,
+                column,
+                required: !required,
+            },
+            Change::ColumnDefaultChanged {
+                schema,
+                table,
+                column,
+                from,
+                to,
+            } => Change::ColumnDefaultChanged {
+                schema,
+                table,
+                column,
+                from: to,
+                to: from,
+            },
+            Change::PrimaryKeyChanged {
+                schema,
+                table,
+                from,
+                to,
+            } => Change::PrimaryKeyChanged {
+                schema,
+                table,
+                from: to,
+                to: from,
+            },
+            Change::ForeignKeyAdded {
+                schema,
+                table,
+                foreign_key,
+            } => Change::ForeignKeyRemoved {
+                schema,
+                table,
+                foreign_key,
+            },
+            Change::ForeignKeyRemoved {
+                schema,
+                table,
+                foreign_key,
+            } => Change::ForeignKeyAdded {
+                schema,
+                table,
+                foreign_key,
+            },
+            Change::UniqueConstraintAdded {
+                schema,
+                table,
+                unique_constraint,
+            } => Change::UniqueConstraintRemoved {
+                schema,
+                table,
+                unique_constraint,
+            },
+            Change::UniqueConstraintRemoved {
+                schema,
+                table,
+                unique_constraint,
+            } => Change::UniqueConstraintAdded {
+                schema,
+                table,
+                unique_constraint,
+            },
+            Change::IndexAdded {
+                schema,
+                table,
+                index,
+            } => Change::IndexRemoved {
+                schema,
+                table,
+                index,
+            },
+            Change::IndexRemoved {
+                schema,
+                table,
+                index,
+            } => Change::IndexAdded {
+                schema,
+                table,
+                index,
+            },
+        }
+    }
+}
+
+/// Order views so that each comes after the others it selects from, which is any of them
+/// whose name appears as a word in its definition. Views in a cycle keep their order.
+fn dependency_order(mut views: Vec<&Table>) -> Vec<&Table> {
+    let mut ordered = Vec::with_capacity(views.len());
+
+    while !views.is_empty() {
+        let position = views
+            .iter()
+            .position(|view| {
+                !views
+                    .iter()
+                    .any(|other| !std::ptr::eq(*view, *other) && selects_from(view, other))
+            })
+            .unwrap_or(0);
+        ordered.push(views.remove(position));
+    }
+
+    ordered
+}
+
+/// Whether the definition of a view names another table or view.
+fn selects_from(view: &Table, other: &Table) -> bool {
+    let Some(definition) = &view.definition else {
+        return false;
+    };
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+    definition
+        .match_indices(other.name.as_str())
+        .any(|(start, name)| {
+            !is_word(definition[..start].chars().next_back())
+                && !is_word(definition[start + name.len()..].chars().next())
+        })
+}
+
+fn drop_table(table: &Table) -> String {
+    format!(
+        "DROP {} {};",
+        match table.kind {
+            TableKind::Table => "TABLE",
+            TableKind::View => "VIEW",
+            TableKind::MaterializedView => "MATERIALIZED VIEW",
+        },
+        qualified(&table.schema, &table.name)
+    )
+}
+
+/// The statements creating a table or view, with its keys, unique constraints, indexes and
+/// comments, but without its foreign keys.
+fn create_table(table: &Table) -> Vec<String> {
+    let name = qualified(&table.schema, &table.name);
+
+    if let Some(definition) = &table.definition {
+        let kind = match table.kind {
+            TableKind::MaterializedView => "MATERIALIZED VIEW",
+            _ => "VIEW",
+        };
+        return vec![format!(
+            "CREATE {kind} {name} AS {};",
+            definition.trim_end_matches(';')
+        )];
+    }
+
+    let mut definitions: Vec<String> = table
+        .columns
+        .iter()
+        .map(|c| format!("    {}", column_definition(c)))
+        .collect();
+    if !table.primary_key.is_empty() {
+        let constraint = table
+            .indexes
+            .iter()
+            .find(|i| i.is_primary)
+            .map(|index| format!("CONSTRAINT {} ", quote(&index.name)))
+            .unwrap_or_default();
+        definitions.push(format!(
+            "    {constraint}PRIMARY KEY ({})",
+            columns(&table.primary_key)
+        ));
+    }
+    for unique_constraint in &table.unique_constraints {
+        definitions.push(format!(
+            "    CONSTRAINT {} UNIQUE ({})",
+            quote(&unique_constraint.name),
+            columns(&unique_constraint.columns)
+        ));
+    }
+
+    let mut statements = vec![format!(
+        "CREATE TABLE {name} (\n{}\n);",
+        definitions.join(",\n")
+    )];
+
+    for index in &table.indexes {
+        let is_constraint = index.is_primary
+            || table
+                .unique_constraints
+                .iter()
+                .any(|u| u.name == index.name);
+        if !is_constraint {
+            statements.push(create_index(&table.schema, &table.name, index));
+        }
+    }
+
+    if let Some(comment) = &table.comment {
+        statements.push(format!("COMMENT ON TABLE {name} IS {};", literal(comment)));
+    }
+    for column in &table.columns {
+        statements.extend(column_comment(&table.schema, &table.name, column));
+    }
+
+    statements
+}
+
+fn column_definition(column: &Column) -> String {
+    // Enum types are qualified, since they needn't live in the schema of the table.
+    let mut sql_type = match (&column.enum_schema, &column.enum_name) {
+        (Some(schema), Some(name)) => {
+            qualified(schema, name) + &"[]".repeat(column.data_type.dimensions as usize)
+        }
+        _ => column.sql_type(),
+    };
+    let mut default = column.default.clone();
+
+    // The sequence behind a serial default is created along with a serial column.
+    if default.as_ref().is_some_and(|d| d.starts_with("nextval(")) {
+        let serial = match column.data_type.name.as_str() {
+            "int2" => Some("smallserial"),
+            "int4" => Some("serial"),
+            "int8" => Some("bigserial"),
+            _ => None,
+        };
+        if let Some(serial) = serial {
+            sql_type = serial.to_string();
+            default = None;
+        }
+    }
+
+    let mut definition = format!("{} {sql_type}", quote(&column.name));
+    match (&column.identity_generation, &column.generation_expression) {
+        (Some(generation), _) => definition.push_str(match generation {
+            IdentityGeneration::Always => " GENERATED ALWAYS AS IDENTITY",
+            IdentityGeneration::ByDefault => " GENERATED BY DEFAULT AS IDENTITY",
+        }),
+        (None, Some(expression)) => {
+            definition.push_str(&format!(" GENERATED ALWAYS AS ({expression}) STORED"))
+        }
+        (None, None) => {}
+    }
+    if column.required {
+        definition.push_str(" NOT NULL");
+    }
+    if let Some(default) = default {
+        definition.push_str(&format!(" DEFAULT {default}"));
+    }
+
+    definition
+}
+
+fn column_comment(schema: &str, table: &str, column: &Column) -> Option<String> {
+    column.comment.as_ref().map(|comment| {
+        format!(
+            "COMMENT ON COLUMN {}.{} IS {};",
+            qualified(schema, table),
+            quote(&column.name),
+            literal(comment)
+        )
+    })
+}
+
+fn create_index(schema: &str, table: &str, index: &Index) -> String {
+    let keys: Vec<String> = index
+        .keys
+        .iter()
+        .map(|key| match key {
+            IndexKey::Column(column) => quote(column),
+            IndexKey::Expression(expression) => format!("({expression})"),
+        })
+        .collect();
+
+    format!(
+        "CREATE {}INDEX {} ON {} USING {} ({}){};",
+        if index.is_unique { "UNIQUE " } else { "" },
+        quote(&index.name),
+        qualified(schema, table),
+        index.method,
+        keys.join(", "),
+        index
+            .predicate
+            .as_ref()
+            .map(|p| format!(" WHERE {p}"))
+            .unwrap_or_default()
+    )
+}
+
+fn add_foreign_key(schema: &str, table: &str, foreign_key: &ForeignKey) -> String {
+    let (columns, referenced_columns): (Vec<String>, Vec<String>) = foreign_key
+        .columns
+        .iter()
+        .map(|c| (quote(&c.column), quote(&c.referenced_column)))
+        .unzip();
+
+    let mut sql = format!(
+        "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({})",
+        qualified(schema, table),
+        quote(&foreign_key.name),
+        columns.join(", "),
+        qualified(
+            &foreign_key.referenced_schema,
+            &foreign_key.referenced_table
+        ),
+        referenced_columns.join(", ")
+    );
+    if let Some(action) = referential_action(foreign_key.on_delete) {
+        sql.push_str(&format!(" ON DELETE {action}"));
+    }
+    if let Some(action) = referential_action(foreign_key.on_update) {
+        sql.push_str(&format!(" ON UPDATE {action}"));
+    }
+    if foreign_key.deferrable {
+        sql.push_str(" DEFERRABLE");
+    }
+    if foreign_key.initially_deferred {
+        sql.push_str(" INITIALLY DEFERRED");
+    }
+    sql.push(';');
+
+    sql
+}
+
+fn referential_action(action: ReferentialAction) -> Option<&'static str> {
+    match action {
+        ReferentialAction::NoAction => None,
+        ReferentialAction::Restrict => Some("RESTRICT"),
+        ReferentialAction::Cascade => Some("CASCADE"),
+        ReferentialAction::SetNull => Some("SET NULL"),
+        ReferentialAction::SetDefault => Some("SET DEFAULT"),
+    }
+}
+
+fn columns(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|c| quote(c))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn qualified(schema: &str, name: &str) -> String {
+    format!("{}.{}", quote(schema), quote(name))
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn enum_labels(enumeration: &Enum) -> String {
+    enumeration
+        .labels
+        .iter()
+        .map(|l| literal(l))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,452 @@
+use std::collections::{HashMap, HashSet};
+
+use sqlx::{MySql, Pool, query_as, query_scalar};
+
+use crate::{
+    Result,
+    catalog::{self, ConstraintKind, KeyColumn, TableIndex},
+    database::{
+        Column, DataType, Database, Enum, IdentityGeneration, Index, IndexKey, Reference,
+        ReferentialAction, Table, TableKind,
+    },
+    error::Error,
+};
+
+// Every text column is cast to CHAR, since MySQL reports some `information_schema` columns
+// with a binary collation that cannot be decoded as a string.
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct TableMetadata {
+    schema: String,
+    name: String,
+    kind: String,
+    definition: Option<String>,
+    comment: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct ColumnMetadata {
+    schema: String,
+    table: String,
+    name: String,
+    kind: String,
+    column_type: String,
+    optional: bool,
+    default: Option<String>,
+    extra: String,
+    generation_expression: Option<String>,
+    length: Option<i64>,
+    precision: Option<i64>,
+    scale: Option<i64>,
+    comment: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct ConstraintMetadata {
+    name: String,
+    kind: String,
+    schema: String,
+    table: String,
+    column: String,
+    referenced_schema: Option<String>,
+    referenced_table: Option<String>,
+    referenced_column: Option<String>,
+    on_delete: Option<String>,
+    on_update: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct IndexMetadata {
+    schema: String,
+    table: String,
+    name: String,
+    column: Option<String>,
+    expression: Option<String>,
+    is_unique: bool,
+    method: String,
+}
+
+/// Introspect a MySQL or MariaDB database into the same snapshot shape as PostgreSQL.
+///
+/// MySQL has no separate schemas within a database, so `schemas` names databases, and
+/// defaults to the database of the connection URL. Enums are declared inline on a column,
+/// so each enum column gets its own enum named `<table>_<column>`.
+pub(crate) async fn fetch(
+    database_url: &str,
+    schemas: Vec<String>,
+    ignore: Vec<String>,
+) -> Result<Database> {
+    let connection = Pool::<MySql>::connect(database_url)
+        .await
+        .map_err(Error::Connection)?;
+
+    let schemas = match schemas.is_empty() {
+        true => query_scalar::<_, Option<String>>("SELECT CAST(DATABASE() AS CHAR)")
+            .fetch_one(&connection)
+            .await?
+            .into_iter()
+            .collect(),
+        false => schemas,
+    };
+
+    if schemas.is_empty() {
+        return Ok(Database {
+            tables: Vec::new(),
+            enums: Vec::new(),
+        });
+    }
+
+    let sql = with_schemas(
+        r#"
+            SELECT
+                CAST(T.`TABLE_SCHEMA` AS CHAR) AS `schema`,
+                CAST(T.`TABLE_NAME` AS CHAR) AS `name`,
+                CAST(T.`TABLE_TYPE` AS CHAR) AS `kind`,
+                CAST(V.`VIEW_DEFINITION` AS CHAR) AS `definition`,
+                CAST(T.`TABLE_COMMENT` AS CHAR) AS `comment`
+            FROM `information_schema`.`TABLES` AS T
+            LEFT JOIN `information_schema`.`VIEWS` AS V
+                ON V.`TABLE_SCHEMA` = T.`TABLE_SCHEMA` AND V.`TABLE_NAME` = T.`TABLE_NAME`
+            WHERE
+                T.`TABLE_TYPE` IN ('BASE TABLE', 'SYSTEM VERSIONED', 'VIEW')
+                AND T.`TABLE_SCHEMA` IN ({schemas})
+        "#,
+        &schemas,
+    );
+    let mut query = query_as::<_, TableMetadata>(&sql);
+    for schema in &schemas {
+        query = query.bind(schema);
+    }
+    let tables: Vec<TableMetadata> = query
+        .fetch_all(&connection)
+        .await?
+        .into_iter()
+        .filter(|t| {
+            !ignore.contains(&t.name) && !ignore.contains(&format!("{}.{}", t.schema, t.name))
+        })
+        .collect();
+
+    let sql = with_schemas(
+        r#"
+            SELECT
+                CAST(C.`TABLE_SCHEMA` AS CHAR) AS `schema`,
+                CAST(C.`TABLE_NAME` AS CHAR) AS `table`,
+                CAST(C.`COLUMN_NAME` AS CHAR) AS `name`,
+                CAST(C.`DATA_TYPE` AS CHAR) AS `kind`,
+                CAST(C.`COLUMN_TYPE` AS CHAR) AS `column_type`,
+                CAST(C.`IS_NULLABLE` = 'YES' AS SIGNED) AS `optional`,
+                CAST(C.`COLUMN_DEFAULT` AS CHAR) AS `default`,
+                CAST(C.`EXTRA` AS CHAR) AS `extra`,
+                CAST(C.`GENERATION_EXPRESSION` AS CHAR) AS `generation_expression`,
+                CAST(C.`CHARACTER_MAXIMUM_LENGTH` AS SIGNED) AS `length`,
+                CAST(C.`NUMERIC_PRECISION` AS SIGNED) AS `precision`,
+                CAST(C.`NUMERIC_SCALE` AS SIGNED) AS `scale`,
+                CAST(C.`COLUMN_COMMENT` AS CHAR) AS `comment`
+            FROM `information_schema`.`COLUMNS` AS C
+            WHERE C.`TABLE_SCHEMA` IN ({schemas})
+            ORDER BY C.`TABLE_SCHEMA`, C.`TABLE_NAME`, C.`ORDINAL_POSITION`
+        "#,
+        &schemas,
+    );
+    let mut query = query_as::<_, ColumnMetadata>(&sql);
+    for schema in &schemas {
+        query = query.bind(schema);
+    }
+    let columns = query.fetch_all(&connection).await?;
+
+    // Every primary key, unique and foreign key constraint touching one of the schemas,
+    // with one row per (constraint, column) pair.
+    let sql = with_schemas(
+        r#"
+            SELECT
+                CAST(K.`CONSTRAINT_NAME` AS CHAR) AS `name`,
+                CAST(TC.`CONSTRAINT_TYPE` AS CHAR) AS `kind`,
+                CAST(K.`TABLE_SCHEMA` AS CHAR) AS `schema`,
+                CAST(K.`TABLE_NAME` AS CHAR) AS `table`,
+                CAST(K.`COLUMN_NAME` AS CHAR) AS `column`,
+                CAST(K.`REFERENCED_TABLE_SCHEMA` AS CHAR) AS `referenced_schema`,
+                CAST(K.`REFERENCED_TABLE_NAME` AS CHAR) AS `referenced_table`,
+                CAST(K.`REFERENCED_COLUMN_NAME` AS CHAR) AS `referenced_column`,
+                CAST(RC.`DELETE_RULE` AS CHAR) AS `on_delete`,
+                CAST(RC.`UPDATE_RULE` AS CHAR) AS `on_update`
+            FROM `information_schema`.`KEY_COLUMN_USAGE` AS K
+            INNER JOIN `information_schema`.`TABLE_CONSTRAINTS` AS TC
+                ON TC.`CONSTRAINT_SCHEMA` = K.`CONSTRAINT_SCHEMA`
+                AND TC.`CONSTRAINT_NAME` = K.`CONSTRAINT_NAME`
+                AND TC.`TABLE_SCHEMA` = K.`TABLE_SCHEMA`
+                AND TC.`TABLE_NAME` = K.`TABLE_NAME`
+            LEFT JOIN `information_schema`.`REFERENTIAL_CONSTRAINTS` AS RC
+                ON RC.`CONSTRAINT_SCHEMA` = K.`CONSTRAINT_SCHEMA`
+                AND RC.`CONSTRAINT_NAME` = K.`CONSTRAINT_NAME`
+                AND RC.`TABLE_NAME` = K.`TABLE_NAME`
+            WHERE
+                TC.`CONSTRAINT_TYPE` IN ('PRIMARY KEY', 'UNIQUE', 'FOREIGN KEY')
+                AND (K.`TABLE_SCHEMA` IN ({schemas}) OR K.`REFERENCED_TABLE_SCHEMA` IN ({schemas}))
+            ORDER BY K.`CONSTRAINT_NAME`, K.`TABLE_SCHEMA`, K.`TABLE_NAME`, K.`ORDINAL_POSITION`
+        "#,
+        &schemas,
+    );
+    let mut query = query_as::<_, ConstraintMetadata>(&sql);
+    for schema in schemas.iter().chain(&schemas) {
+        query = query.bind(schema);
+    }
+    let constraints = query.fetch_all(&connection).await?;
+
+    // Functional key parts (MySQL 8.0.13+) have no column but an expression. Older MySQL and
+    // MariaDB do not have the "EXPRESSION" column at all.
+    let has_expressions = query_scalar::<_, bool>(
+        r#"
+            SELECT CAST(COUNT(*) > 0 AS SIGNED)
+            FROM `information_schema`.`COLUMNS`
+            WHERE
+                `TABLE_SCHEMA` = 'information_schema'
+                AND `TABLE_NAME` = 'STATISTICS'
+                AND `COLUMN_NAME` = 'EXPRESSION'
+        "#,
+    )
+    .fetch_one(&connection)
+    .await?;
+
+    let sql = with_schemas(
+        r#"
+            SELECT
+                CAST(S.`TABLE_SCHEMA` AS CHAR) AS `schema`,
+                CAST(S.`TABLE_NAME` AS CHAR) AS `table`,
+                CAST(S.`INDEX_NAME` AS CHAR) AS `name`,
+                CAST(S.`COLUMN_NAME` AS CHAR) AS `column`,
+                CAST({expression} AS CHAR) AS `expression`,
+                CAST(S.`NON_UNIQUE` = 0 AS SIGNED) AS `is_unique`,
+                CAST(LOWER(S.`INDEX_TYPE`) AS CHAR) AS `method`
+            FROM `information_schema`.`STATISTICS` AS S
+            WHERE S.`TABLE_SCHEMA` IN ({schemas})
+            ORDER BY S.`TABLE_SCHEMA`, S.`TABLE_NAME`, S.`INDEX_NAME`, S.`SEQ_IN_INDEX`
+        "#,
+        &schemas,
+    )
+    .replace(
+        "{expression}",
+        match has_expressions {
+            true => "S.`EXPRESSION`",
+            false => "NULL",
+        },
+    );
+    let mut query = query_as::<_, IndexMetadata>(&sql);
+    for schema in &schemas {
+        query = query.bind(schema);
+    }
+    let index_columns = query.fetch_all(&connection).await?;
+
+    // Columns of excluded tables are left out before their enums are collected.
+    let included: HashSet<(&str, &str)> = tables
+        .iter()
+        .map(|t| (t.schema.as_str(), t.name.as_str()))
+        .collect();
+    let columns: Vec<ColumnMetadata> = columns
+        .into_iter()
+        .filter(|c| included.contains(&(c.schema.as_str(), c.table.as_str())))
+        .collect();
+
+    let mut enums: Vec<Enum> = Vec::new();
+    let mut columns_by_table: HashMap<(String, String), Vec<Column>> = HashMap::new();
+    for column in columns {
+        let enum_name = match column.kind.as_str() {
+            "enum" => {
+                let name = format!("{}_{}", column.table, column.name);
+                enums.push(Enum {
+                    schema: column.schema.clone(),
+                    name: name.clone(),
+                    labels: enum_labels(&column.column_type),
+                });
+                Some(name)
+            }
+            _ => None,
+        };
+
+        let is_identity = column.extra.contains("auto_increment");
+        let is_generated = [
+            "VIRTUAL GENERATED",
+            "STORED GENERATED",
+            "PERSISTENT GENERATED",
+        ]
+        .iter()
+        .any(|generated| column.extra.contains(generated));
+
+        // MariaDB reports the absence of a default on a nullable column as the string "NULL".
+        let default = match (is_generated, column.default) {
+            (true, _) => None,
+            (false, Some(default)) if default == "NULL" => None,
+            (false, default) => default,
+        };
+
+        columns_by_table
+            .entry((column.schema.clone(), column.table))
+            .or_default()
+            .push(Column {
+                name: column.name,
+                kind: column.kind.clone(),
+                required: !column.optional,
+                referenced_by: Vec::new(),
+                references: None,
+                is_primary_key: false,
+                is_unique: false,
+                default,
+                is_identity,
+                identity_generation: is_identity.then_some(IdentityGeneration::ByDefault),
+                is_generated,
+                generation_expression: match is_generated {
+                    true => column.generation_expression.filter(|e| !e.is_empty()),
+                    false => None,
+                },
+                enum_schema: enum_name.as_ref().map(|_| column.schema.clone()),
+                enum_name,
+                comment: column.comment.filter(|c| !c.is_empty()),
+                data_type: DataType {
+                    name: column.kind,
+                    udt_name: column.column_type,
+                    dimensions: 0,
+                    length: column.length.and_then(|l| i32::try_from(l).ok()),
+                    precision: column.precision.and_then(|p| i32::try_from(p).ok()),
+                    scale: column.scale.and_then(|s| i32::try_from(s).ok()),
+                    domains: Vec::new(),
+                },
+            });
+    }
+
+    let mut tables: Vec<Table> = tables
+        .into_iter()
+        .map(|table| {
+            let (kind, comment) = match table.kind.as_str() {
+                // MySQL reports the comment of every view as "VIEW".
+                "VIEW" => (TableKind::View, None),
+                _ => (TableKind::Table, table.comment.filter(|c| !c.is_empty())),
+            };
+
+            Table {
+                columns: columns_by_table
+                    .remove(&(table.schema.clone(), table.name.clone()))
+                    .unwrap_or_default(),
+                schema: table.schema,
+                name: table.name,
+                kind,
+                definition: table.definition,
+                comment,
+                primary_key: Vec::new(),
+                foreign_keys: Vec::new(),
+                unique_constraints: Vec::new(),
+                indexes: Vec::new(),
+            }
+        })
+        .collect();
+
+    let keys: Vec<KeyColumn> = constraints
+        .into_iter()
+        .map(|c| KeyColumn {
+            kind: match c.kind.as_str() {
+                "PRIMARY KEY" => ConstraintKind::PrimaryKey,
+                "UNIQUE" => ConstraintKind::Unique,
+                _ => ConstraintKind::ForeignKey,
+            },
+            referenced: match (c.referenced_schema, c.referenced_table, c.referenced_column) {
+                (Some(schema), Some(table), Some(column)) => Some(Reference {
+                    schema,
+                    table,
+                    column,
+                }),
+                _ => None,
+            },
+            on_delete: referential_action(c.on_delete.as_deref()),
+            on_update: referential_action(c.on_update.as_deref()),
+            deferrable: false,
+            initially_deferred: false,
+            name: c.name,
+            schema: c.schema,
+            table: c.table,
+            column: c.column,
+        })
+        .collect();
+
+    // The rows are ordered by index, so a row either continues the last index or starts one.
+    let mut indexes: Vec<TableIndex> = Vec::new();
+    for row in index_columns {
+        let is_continued = indexes.last().is_some_and(|i| {
+            i.schema == row.schema && i.table == row.table && i.index.name == row.name
+        });
+
+        let index = match is_continued {
+            true => &mut indexes.last_mut().expect("index was just checked").index,
+            false => {
+                indexes.push(TableIndex {
+                    schema: row.schema,
+                    table: row.table,
+                    index: Index {
+                        is_primary: row.name == "PRIMARY",
+                        name: row.name,
+                        keys: Vec::new(),
+                        is_unique: row.is_unique,
+                        method: row.method,
+                        predicate: None,
+                    },
+                });
+                &mut indexes.last_mut().expect("index was just pushed").index
+            }
+        };
+
+        match (row.column, row.expression) {
+            (Some(column), _) => index.keys.push(IndexKey::Column(column)),
+            (None, Some(expression)) => index.keys.push(IndexKey::Expression(expression)),
+            (None, None) => {}
+        }
+    }
+
+    catalog::assemble(&mut tables, &keys, &indexes);
+
+    Ok(Database { tables, enums })
+}
+
+/// Expand the `{schemas}` markers of a query into one placeholder per schema, since MySQL has
+/// no array parameters.
+fn with_schemas(sql: &str, schemas: &[String]) -> String {
+    sql.replace("{schemas}", &vec!["?"; schemas.len()].join(", "))
+}
+
+/// Parse the labels out of a column type such as `enum('a','b''c')`.
+fn enum_labels(column_type: &str) -> Vec<String> {
+    let Some(values) = column_type
+        .strip_prefix("enum(")
+        .and_then(|v| v.strip_suffix(')'))
+    else {
+        return Vec::new();
+    };
+
+    let mut labels = Vec::new();
+    let mut label = String::new();
+    let mut chars = values.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (false, '\'') => quoted = true,
+            (false, ',') => labels.push(std::mem::take(&mut label)),
+            (false, _) => {}
+            (true, '\'') if chars.peek() == Some(&'\'') => {
+                chars.next();
+                label.push('\'');
+            }
+            (true, '\'') => quoted = false,
+            (true, c) => label.push(c),
+        }
+    }
+    labels.push(label);
+
+    labels
+}
+
+/// Map a `REFERENTIAL_CONSTRAINTS.DELETE_RULE` or `UPDATE_RULE` to its action.
+fn referential_action(rule: Option<&str>) -> ReferentialAction {
+    match rule {
+        Some("RESTRICT") => ReferentialAction::Restrict,
+        Some("CASCADE") => ReferentialAction::Cascade,
+        Some("SET NULL") => ReferentialAction::SetNull,
+        Some("SET DEFAULT") => ReferentialAction::SetDefault,
+        _ => ReferentialAction::NoAction,
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+
+use sqlx::{Pool, Postgres, query_as};
+
+use crate::{
+    Result,
+    catalog::{self, ConstraintKind, KeyColumn, TableIndex},
+    database::{
+        Column, DataType, Database, Enum, IdentityGeneration, Index, IndexKey, Reference,
+        ReferentialAction, Table, TableKind,
+    },
+    error::Error,
+};
+
+const DEFAULT_SCHEMA: &str = "public";
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct TableMetadata {
+    id: i64,
+    schema: String,
+    name: String,
+    kind: String,
+    definition: Option<String>,
+    comment: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct ColumnMetadata {
+    table_id: i64,
+    name: String,
+    kind: String,
+    optional: bool,
+    default: Option<String>,
+    identity: String,
+    generated: String,
+    enum_name: Option<String>,
+    enum_schema: Option<String>,
+    comment: Option<String>,
+    type_name: String,
+    udt_name: String,
+    dimensions: i32,
+    length: Option<i32>,
+    precision: Option<i32>,
+    scale: Option<i32>,
+    domains: Vec<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct ConstraintMetadata {
+    name: String,
+    kind: String,
+    schema: String,
+    table: String,
+    column: String,
+    referenced_schema: Option<String>,
+    referenced_table: Option<String>,
+    referenced_column: Option<String>,
+    on_delete: String,
+    on_update: String,
+    deferrable: bool,
+    initially_deferred: bool,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct IndexMetadata {
+    schema: String,
+    table: String,
+    name: String,
+    /// One entry per key, holding the column name, or NULL for an expression key.
+    key_columns: Vec<Option<String>>,
+    /// One entry per key, holding the expression, or NULL for a column key.
+    key_expressions: Vec<Option<String>>,
+    is_unique: bool,
+    is_primary: bool,
+    method: String,
+    predicate: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct EnumMetadata {
+    schema: String,
+    name: String,
+    labels: Vec<String>,
+}
+
+/// Introspect a PostgreSQL database using a fixed number of set-based queries against
+/// `pg_catalog`, regardless of how many tables and columns it contains.
+pub(crate) async fn fetch(
+    database_url: &str,
+    schemas: Vec<String>,
+    ignore: Vec<String>,
+) -> Result<Database> {
+    let connection = Pool::<Postgres>::connect(database_url)
+        .await
+        .map_err(Error::Connection)?;
+
+    let schemas = match schemas.is_empty() {
+        true => vec![DEFAULT_SCHEMA.to_string()],
+        false => schemas,
+    };
+
+    let tables = query_as::<_, TableMetadata>(
+        r#"
+            SELECT
+                C."oid"::int8 AS "id",
+                N."nspname" AS "schema",
+                C."relname" AS "name",
+                C."relkind"::text AS "kind",
+                CASE
+                    WHEN C."relkind" IN ('v', 'm') THEN pg_get_viewdef(C."oid", true)
+                END AS "definition",
+                obj_description(C."oid", 'pg_class') AS "comment"
+            FROM "pg_catalog"."pg_class" AS C
+            INNER JOIN "pg_catalog"."pg_namespace" AS N
+                ON N."oid" = C."relnamespace"
+            WHERE
+                C."relkind" IN ('r', 'p', 'v', 'm')
+                AND N."nspname" = ANY($1)
+                AND NOT (C."relname" = ANY($2))
+                AND NOT ((N."nspname" || '.' || C."relname") = ANY($2))
+        "#,
+    )
+    .bind(&schemas)
+    .bind(ignore)
+    .fetch_all(&connection)
+    .await?;
+
+    let table_ids: Vec<i64> = tables.iter().map(|t| t.id).collect();
+
+    // The "kind" expression mirrors how `information_schema.columns.data_type` is derived.
+    let columns = query_as::<_, ColumnMetadata>(
+        r#"
+            SELECT
+                A."attrelid"::int8 AS "table_id",
+                A."attname" AS "name",
+                CASE
+                    WHEN T."typtype" = 'd' THEN
+                        CASE
+                            WHEN BT."typelem" <> 0 AND BT."typlen" = -1 THEN 'ARRAY'
+                            WHEN BTN."nspname" = 'pg_catalog' THEN format_type(T."typbasetype", NULL)
+                            ELSE 'USER-DEFINED'
+                        END
+                    ELSE
+                        CASE
+                            WHEN T."typelem" <> 0 AND T."typlen" = -1 THEN 'ARRAY'
+                            WHEN TN."nspname" = 'pg_catalog' THEN format_type(A."atttypid", NULL)
+                            ELSE 'USER-DEFINED'
+                        END
+                END AS "kind",
+                NOT (A."attnotnull" OR (T."typtype" = 'd' AND T."typnotnull")) AS "optional",
+                pg_get_expr(D."adbin", D."adrelid", true) AS "default",
+                A."attidentity"::text AS "identity",
+                A."attgenerated"::text AS "generated",
+                CASE WHEN ET."typtype" = 'e' THEN ET."typname"::text END AS "enum_name",
+                CASE WHEN ET."typtype" = 'e' THEN ETN."nspname"::text END AS "enum_schema",
+                col_description(A."attrelid", A."attnum") AS "comment",
+                ET."typname"::text AS "type_name",
+                RT."typname"::text AS "udt_name",
+                CASE
+                    WHEN RT."typelem" <> 0 AND RT."typlen" = -1 THEN GREATEST(A."attndims", 1)
+                    ELSE 0
+                END AS "dimensions",
+                -- Decode the type modifier the way format_type does, e.g. varchar(64) is stored
+                -- as 68 and numeric(10,2) as (10 << 16 | 2) + 4.
+                CASE
+                    WHEN R."typmod" < 0 THEN NULL
+                    WHEN ET."oid" IN ('varchar'::regtype, 'bpchar'::regtype) THEN R."typmod" - 4
+                    WHEN ET."oid" IN ('bit'::regtype, 'varbit'::regtype) THEN R."typmod"
+                END AS "length",
+                CASE ET."oid"
+                    WHEN 'int2'::regtype THEN 16
+                    WHEN 'int4'::regtype THEN 32
+                    WHEN 'int8'::regtype THEN 64
+                    WHEN 'float4'::regtype THEN 24
+                    WHEN 'float8'::regtype THEN 53
+                    WHEN 'numeric'::regtype THEN
+                        CASE WHEN R."typmod" >= 0 THEN ((R."typmod" - 4) >> 16) & 65535 END
+                END AS "precision",
+                CASE ET."oid"
+                    WHEN 'int2'::regtype THEN 0
+                    WHEN 'int4'::regtype THEN 0
+                    WHEN 'int8'::regtype THEN 0
+                    WHEN 'numeric'::regtype THEN
+                        CASE WHEN R."typmod" >= 0 THEN (R."typmod" - 4) & 65535 END
+                END AS "scale",
+                R."domains"
+            FROM "pg_catalog"."pg_attribute" AS A
+            LEFT JOIN "pg_catalog"."pg_attrdef" AS D
+                ON D."adrelid" = A."attrelid" AND D."adnum" = A."attnum"
+            INNER JOIN "pg_catalog"."pg_type" AS T
+                ON T."oid" = A."atttypid"
+            INNER JOIN "pg_catalog"."pg_namespace" AS TN
+                ON TN."oid" = T."typnamespace"
+            LEFT JOIN "pg_catalog"."pg_type" AS BT
+                ON T."typtype" = 'd' AND BT."oid" = T."typbasetype"
+            LEFT JOIN "pg_catalog"."pg_namespace" AS BTN
+                ON BTN."oid" = BT."typnamespace"
+            CROSS JOIN LATERAL (
+                -- Resolve the chain of domains down to the underlying type and its modifier.
+                WITH RECURSIVE "chain"("type_id", "typmod", "domains") AS (
+                    SELECT A."atttypid", A."atttypmod", ARRAY[]::name[]
+                    UNION ALL
+                    SELECT DT."typbasetype", DT."typtypmod", CH."domains" || DT."typname"
+                    FROM "chain" AS CH
+                    INNER JOIN "pg_catalog"."pg_type" AS DT
+                        ON DT."oid" = CH."type_id"
+                    WHERE DT."typtype" = 'd'
+                )
+                SELECT CH."type_id", CH."typmod", CH."domains"::text[] AS "domains"
+                FROM "chain" AS CH
+                ORDER BY cardinality(CH."domains") DESC
+                LIMIT 1
+            ) AS R
+            INNER JOIN "pg_catalog"."pg_type" AS RT
+                ON RT."oid" = R."type_id"
+            INNER JOIN "pg_catalog"."pg_type" AS ET
+                ON ET."oid" = CASE
+                    WHEN RT."typelem" <> 0 AND RT."typlen" = -1 THEN RT."typelem"
+                    ELSE RT."oid"
+                END
+            INNER JOIN "pg_catalog"."pg_namespace" AS ETN
+                ON ETN."oid" = ET."typnamespace"
+            WHERE
+                A."attrelid" = ANY($1::int8[]::oid[])
+                AND A."attnum" > 0
+                AND NOT A."attisdropped"
+            ORDER BY A."attrelid", A."attnum"
+        "#,
+    )
+    .bind(&table_ids)
+    .fetch_all(&connection)
+    .await?;
+
+    // Every primary key, unique and foreign key constraint touching one of the tables,
+    // unnested into one row per (constraint, column) pair.
+    let constraints = query_as::<_, ConstraintMetadata>(
+        r#"
+            SELECT
+                CON."conname" AS "name",
+                CON."contype"::text AS "kind",
+                SN."nspname" AS "schema",
+                S."relname" AS "table",
+                SA."attname" AS "column",
+                RN."nspname" AS "referenced_schema",
+                R."relname" AS "referenced_table",
+                RA."attname" AS "referenced_column",
+                CON."confdeltype"::text AS "on_delete",
+                CON."confupdtype"::text AS "on_update",
+                CON."condeferrable" AS "deferrable",
+                CON."condeferred" AS "initially_deferred"
+            FROM "pg_catalog"."pg_constraint" AS CON
+            CROSS JOIN LATERAL unnest(CON."conkey", CON."confkey")
+                WITH ORDINALITY AS K("attnum", "referenced_attnum", "position")
+            INNER JOIN "pg_catalog"."pg_class" AS S
+                ON S."oid" = CON."conrelid"
+            INNER JOIN "pg_catalog"."pg_namespace" AS SN
+                ON SN."oid" = S."relnamespace"
+            INNER JOIN "pg_catalog"."pg_attribute" AS SA
+                ON SA."attrelid" = CON."conrelid" AND SA."attnum" = K."attnum"
+            LEFT JOIN "pg_catalog"."pg_class" AS R
+                ON R."oid" = CON."confrelid"
+            LEFT JOIN "pg_catalog"."pg_namespace" AS RN
+                ON RN."oid" = R."relnamespace"
+            LEFT JOIN "pg_catalog"."pg_attribute" AS RA
+                ON RA."attrelid" = CON."confrelid" AND RA."attnum" = K."referenced_attnum"
+            WHERE
+                CON."contype" IN ('p', 'u', 'f')
+                AND (
+                    CON."conrelid" = ANY($1::int8[]::oid[])
+                    OR CON."confrelid" = ANY($1::int8[]::oid[])
+                )
+            ORDER BY CON."conname", K."position"
+        "#,
+    )
+    .bind(&table_ids)
+    .fetch_all(&connection)
+    .await?;
+
+    // Each key is either a column, when "indkey" holds an attribute number, or an expression
+    // when it holds 0. Both arrays have one entry per key so the key order is kept.
+    let indexes = query_as::<_, IndexMetadata>(
+        r#"
+            SELECT
+                TN."nspname" AS "schema",
+                T."relname" AS "table",
+                IC."relname" AS "name",
+                ARRAY(
+                    SELECT A."attname"::text
+                    FROM generate_series(0, I."indnkeyatts" - 1) AS K("position")
+                    LEFT JOIN "pg_catalog"."pg_attribute" AS A
+                        ON A."attrelid" = I."indrelid" AND A."attnum" = I."indkey"[K."position"]
+                    ORDER BY K."position"
+                ) AS "key_columns",
+                ARRAY(
+                    SELECT CASE
+                        WHEN I."indkey"[K."position"] = 0
+                        THEN pg_get_indexdef(I."indexrelid", K."position" + 1, true)
+                    END
+                    FROM generate_series(0, I."indnkeyatts" - 1) AS K("position")
+                    ORDER BY K."position"
+                ) AS "key_expressions",
+                I."indisunique" AS "is_unique",
+                I."indisprimary" AS "is_primary",
+                AM."amname"::text AS "method",
+                pg_get_expr(I."indpred", I."indrelid", true) AS "predicate"
+            FROM "pg_catalog"."pg_index" AS I
+            INNER JOIN "pg_catalog"."pg_class" AS IC
+                ON IC."oid" = I."indexrelid"
+            INNER JOIN "pg_catalog"."pg_am" AS AM
+                ON AM."oid" = IC."relam"
+            INNER JOIN "pg_catalog"."pg_class" AS T
+                ON T."oid" = I."indrelid"
+            INNER JOIN "pg_catalog"."pg_namespace" AS TN
+                ON TN."oid" = T."relnamespace"
+            WHERE I."indrelid" = ANY($1::int8[]::oid[])
+            ORDER BY IC."relname"
+        "#,
+    )
+    .bind(&table_ids)
+    .fetch_all(&connection)
+    .await?;
+
+    // Enums in the introspected schemas, plus those living elsewhere but used by a column,
+    // directly or as the element of an array or the base of a domain.
+    let enums = query_as::<_, EnumMetadata>(
+        r#"
+            WITH RECURSIVE "used"("type_id") AS (
+                SELECT A."atttypid"
+                FROM "pg_catalog"."pg_attribute" AS A
+                WHERE A."attrelid" = ANY($2::int8[]::oid[])
+                UNION
+                SELECT U."type_id"
+                FROM "used"
+                INNER JOIN "pg_catalog"."pg_type" AS T
+                    ON T."oid" = "used"."type_id"
+                CROSS JOIN LATERAL (VALUES (T."typelem"), (T."typbasetype")) AS U("type_id")
+                WHERE U."type_id" <> 0
+            )
+            SELECT
+                N."nspname" AS "schema",
+                T."typname" AS "name",
+                ARRAY(
+                    SELECT E."enumlabel"::text
+                    FROM "pg_catalog"."pg_enum" AS E
+                    WHERE E."enumtypid" = T."oid"
+                    ORDER BY E."enumsortorder"
+                ) AS "labels"
+            FROM "pg_catalog"."pg_type" AS T
+            INNER JOIN "pg_catalog"."pg_namespace" AS N
+                ON N."oid" = T."typnamespace"
+            WHERE
+                T."typtype" = 'e'
+                AND (
+                    N."nspname" = ANY($1)
+                    OR T."oid" IN (SELECT "type_id" FROM "used")
+                )
+        "#,
+    )
+    .bind(&schemas)
+    .bind(&table_ids)
+    .fetch_all(&connection)
+    .await?;
+
+    let mut columns_by_table: HashMap<i64, Vec<Column>> = HashMap::new();
+    for column in columns {
+        let identity_generation = match column.identity.as_str() {
+            "a" => Some(IdentityGeneration::Always),
+            "d" => Some(IdentityGeneration::ByDefault),
+            _ => None,
+        };
+
+        // Generated columns keep their expression in "pg_attrdef" as well.
+        let is_generated = !column.generated.is_empty();
+        let (default, generation_expression) = match is_generated {
+            true => (None, column.default),
+            false => (column.default, None),
+        };
+
+        columns_by_table
+            .entry(column.table_id)
+            .or_default()
+            .push(Column {
+                name: column.name,
+                kind: column.kind,
+                is_primary_key: false,
+                is_unique: false,
+                required: !column.optional,
+                referenced_by: Vec::new(),
+                references: None,
+                default,
+                is_identity: identity_generation.is_some(),
+                identity_generation,
+                is_generated,
+                generation_expression,
+                enum_name: column.enum_name,
+                enum_schema: column.enum_schema,
+                comment: column.comment,
+                data_type: DataType {
+                    name: column.type_name,
+                    udt_name: column.udt_name,
+                    dimensions: column.dimensions,
+                    length: column.length,
+                    precision: column.precision,
+                    scale: column.scale,
+                    domains: column.domains,
+                },
+            });
+    }
+
+    let mut tables: Vec<Table> = tables
+        .into_iter()
+        .map(|table| {
+            let kind = match table.kind.as_str() {
+                "v" => TableKind::View,
+                "m" => TableKind::MaterializedView,
+                _ => TableKind::Table,
+            };
+
+            Table {
+                columns: columns_by_table.remove(&table.id).unwrap_or_default(),
+                schema: table.schema,
+                name: table.name,
+                kind,
+                definition: table.definition.map(|d| d.trim().to_string()),
+                comment: table.comment,
+                primary_key: Vec::new(),
+                foreign_keys: Vec::new(),
+                unique_constraints: Vec::new(),
+                indexes: Vec::new(),
+            }
+        })
+        .collect();
+
+    let keys: Vec<KeyColumn> = constraints
+        .into_iter()
+        .map(|c| KeyColumn {
+            kind: match c.kind.as_str() {
+                "p" => ConstraintKind::PrimaryKey,
+                "u" => ConstraintKind::Unique,
+                _ => ConstraintKind::ForeignKey,
+            },
+            referenced: match (c.referenced_schema, c.referenced_table, c.referenced_column) {
+                (Some(schema), Some(table), Some(column)) => Some(Reference {
+                    schema,
+                    table,
+                    column,
+                }),
+                _ => None,
+            },
+            on_delete: referential_action(&c.on_delete),
+            on_update: referential_action(&c.on_update),
+            deferrable: c.deferrable,
+            initially_deferred: c.initially_deferred,
+            name: c.name,
+            schema: c.schema,
+            table: c.table,
+            column: c.column,
+        })
+        .collect();
+
+    let indexes: Vec<TableIndex> = indexes
+        .into_iter()
+        .map(|i| TableIndex {
+            schema: i.schema,
+            table: i.table,
+            index: Index {
+                name: i.name,
+                keys: i
+                    .key_columns
+                    .into_iter()
+                    .zip(i.key_expressions)
+                    .filter_map(|key| match key {
+                        (Some(column), _) => Some(IndexKey::Column(column)),
+                        (None, Some(expression)) => Some(IndexKey::Expression(expression)),
+                        (None, None) => None,
+                    })
+                    .collect(),
+                is_unique: i.is_unique,
+                is_primary: i.is_primary,
+                method: i.method,
+                predicate: i.predicate,
+            },
+        })
+        .collect();
+
+    catalog::assemble(&mut tables, &keys, &indexes);
+
+    let enums = enums
+        .into_iter()
+        .map(|e| Enum {
+            schema: e.schema,
+            name: e.name,
+            labels: e.labels,
+        })
+        .collect();
+
+    Ok(Database { tables, enums })
+}
+
+/// Map a `pg_constraint.confdeltype` or `confupdtype` code to its action.
+fn referential_action(code: &str) -> ReferentialAction {
+    match code {
+        "r" => ReferentialAction::Restrict,
+        "c" => ReferentialAction::Cascade,
+        "n" => ReferentialAction::SetNull,
+        "d" => ReferentialAction::SetDefault,
+        _ => ReferentialAction::NoAction,
+    }
+}
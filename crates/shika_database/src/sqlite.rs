@@ -0,0 +1,639 @@
+use std::{collections::HashMap, str::FromStr};
+
+use sqlx::{Pool, Sqlite, query_as, sqlite::SqliteConnectOptions};
+
+use crate::{
+    Result,
+    catalog::{self, ConstraintKind, KeyColumn, TableIndex},
+    database::{
+        Column, DataType, Database, IdentityGeneration, Index, IndexKey, Reference,
+        ReferentialAction, Table, TableKind,
+    },
+    error::Error,
+};
+
+const DEFAULT_SCHEMA: &str = "main";
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct TableMetadata {
+    name: String,
+    kind: String,
+    sql: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct ColumnMetadata {
+    table: String,
+    name: String,
+    declared_type: String,
+    not_null: bool,
+    default: Option<String>,
+    pk: i64,
+    hidden: i64,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct ForeignKeyMetadata {
+    table: String,
+    id: i64,
+    column: String,
+    referenced_table: String,
+    referenced_column: Option<String>,
+    on_delete: String,
+    on_update: String,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct IndexMetadata {
+    table: String,
+    name: String,
+    is_unique: bool,
+    origin: String,
+    cid: i64,
+    column: Option<String>,
+    sql: Option<String>,
+}
+
+/// Introspect a SQLite database file, e.g. `sqlite://path/to/app.db`.
+///
+/// The schemas are the attached databases, which default to `main`. The file is opened
+/// read-only, and is never created. SQLite has no comments or enum types, and foreign keys
+/// have no names, so they are named `<table>_<columns>_fkey` as PostgreSQL would. Generated
+/// columns are detected, but their expressions are only kept in the `CREATE TABLE` statement.
+pub(crate) async fn fetch(
+    database_url: &str,
+    schemas: Vec<String>,
+    ignore: Vec<String>,
+) -> Result<Database> {
+    let options = SqliteConnectOptions::from_str(database_url)
+        .map_err(Error::Connection)?
+        .read_only(true)
+        .create_if_missing(false);
+
+    let connection = Pool::<Sqlite>::connect_with(options)
+        .await
+        .map_err(Error::Connection)?;
+
+    let schemas = match schemas.is_empty() {
+        true => vec![DEFAULT_SCHEMA.to_string()],
+        false => schemas,
+    };
+
+    let mut tables: Vec<Table> = Vec::new();
+    let mut keys: Vec<KeyColumn> = Vec::new();
+    let mut indexes: Vec<TableIndex> = Vec::new();
+
+    // The catalog of an attached database can only be addressed through its name, so each
+    // schema takes its own fixed set of queries.
+    for schema in &schemas {
+        let catalog = format!("\"{}\".\"sqlite_schema\"", schema.replace('"', "\"\""));
+
+        let table_rows = query_as::<_, TableMetadata>(&format!(
+            r#"
+                SELECT
+                    M."name" AS "name",
+                    M."type" AS "kind",
+                    M."sql" AS "sql"
+                FROM {catalog} AS M
+                WHERE M."type" IN ('table', 'view') AND M."name" NOT LIKE 'sqlite_%'
+            "#
+        ))
+        .fetch_all(&connection)
+        .await?
+        .into_iter()
+        .filter(|t| !ignore.contains(&t.name) && !ignore.contains(&format!("{schema}.{}", t.name)))
+        .collect::<Vec<_>>();
+
+        let column_rows = query_as::<_, ColumnMetadata>(&format!(
+            r#"
+                SELECT
+                    M."name" AS "table",
+                    C."name" AS "name",
+                    C."type" AS "declared_type",
+                    C."notnull" AS "not_null",
+                    C."dflt_value" AS "default",
+                    C."pk" AS "pk",
+                    C."hidden" AS "hidden"
+                FROM {catalog} AS M, pragma_table_xinfo(M."name", $1) AS C
+                WHERE M."type" IN ('table', 'view') AND M."name" NOT LIKE 'sqlite_%'
+                ORDER BY M."name", C."cid"
+            "#
+        ))
+        .bind(schema)
+        .fetch_all(&connection)
+        .await?;
+
+        let foreign_keys = query_as::<_, ForeignKeyMetadata>(&format!(
+            r#"
+                SELECT
+                    M."name" AS "table",
+                    F."id" AS "id",
+                    F."from" AS "column",
+                    F."table" AS "referenced_table",
+                    F."to" AS "referenced_column",
+                    F."on_delete" AS "on_delete",
+                    F."on_update" AS "on_update"
+                FROM {catalog} AS M, pragma_foreign_key_list(M."name", $1) AS F
+                WHERE M."type" = 'table'
+                ORDER BY M."name", F."id", F."seq"
+            "#
+        ))
+        .bind(schema)
+        .fetch_all(&connection)
+        .await?;
+
+        let index_columns = query_as::<_, IndexMetadata>(&format!(
+            r#"
+                SELECT
+                    M."name" AS "table",
+                    I."name" AS "name",
+                    I."unique" AS "is_unique",
+                    I."origin" AS "origin",
+                    X."cid" AS "cid",
+                    X."name" AS "column",
+                    S."sql" AS "sql"
+                FROM {catalog} AS M, pragma_index_list(M."name", $1) AS I,
+                    pragma_index_xinfo(I."name", $1) AS X
+                LEFT JOIN {catalog} AS S
+                    ON S."type" = 'index' AND S."name" = I."name"
+                WHERE M."type" = 'table' AND X."key" = 1
+                ORDER BY M."name", I."name", X."seqno"
+            "#
+        ))
+        .bind(schema)
+        .fetch_all(&connection)
+        .await?;
+
+        // The rows of every table, grouped in one pass, in the order of the queries.
+        let mut columns_by_table: HashMap<&str, Vec<&ColumnMetadata>> = HashMap::new();
+        for column in &column_rows {
+            columns_by_table
+                .entry(column.table.as_str())
+                .or_default()
+                .push(column);
+        }
+        let mut foreign_keys_by_table: HashMap<&str, Vec<&ForeignKeyMetadata>> = HashMap::new();
+        for foreign_key in &foreign_keys {
+            foreign_keys_by_table
+                .entry(foreign_key.table.as_str())
+                .or_default()
+                .push(foreign_key);
+        }
+        let mut indexes_by_table: HashMap<&str, Vec<&IndexMetadata>> = HashMap::new();
+        for row in &index_columns {
+            indexes_by_table
+                .entry(row.table.as_str())
+                .or_default()
+                .push(row);
+        }
+
+        for table in table_rows {
+            let autoincrement = table
+                .sql
+                .as_deref()
+                .map(autoincrement_columns)
+                .unwrap_or_default();
+
+            let table_columns = columns_by_table
+                .get(table.name.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+
+            let mut primary_key: Vec<&ColumnMetadata> =
+                table_columns.iter().copied().filter(|c| c.pk > 0).collect();
+            primary_key.sort_by_key(|c| c.pk);
+
+            // A single `INTEGER PRIMARY KEY` column is an alias of the rowid, which SQLite
+            // assigns itself.
+            let rowid_alias = match primary_key.as_slice() {
+                [column] if column.declared_type.eq_ignore_ascii_case("integer") => {
+                    Some(column.name.clone())
+                }
+                _ => None,
+            };
+
+            keys.extend(primary_key.iter().map(|c| KeyColumn {
+                name: format!("{}_pkey", table.name),
+                kind: ConstraintKind::PrimaryKey,
+                schema: schema.clone(),
+                table: table.name.clone(),
+                column: c.name.clone(),
+                referenced: None,
+                on_delete: ReferentialAction::NoAction,
+                on_update: ReferentialAction::NoAction,
+                deferrable: false,
+                initially_deferred: false,
+            }));
+
+            let columns: Vec<Column> = table_columns
+                .iter()
+                .map(|c| {
+                    let is_identity = rowid_alias.as_ref() == Some(&c.name);
+                    let is_autoincrement = autoincrement
+                        .iter()
+                        .any(|column| column.eq_ignore_ascii_case(&c.name));
+                    let (name, length, precision, scale) = parse_type(&c.declared_type);
+
+                    Column {
+                        name: c.name.clone(),
+                        kind: name.clone(),
+                        required: c.not_null || is_identity,
+                        referenced_by: Vec::new(),
+                        references: None,
+                        is_primary_key: false,
+                        is_unique: false,
+                        default: c.default.clone(),
+                        is_identity,
+                        identity_generation: match (is_identity, is_autoincrement) {
+                            (true, true) => Some(IdentityGeneration::Always),
+                            (true, false) => Some(IdentityGeneration::ByDefault),
+                            _ => None,
+                        },
+                        // Hidden 2 and 3 are virtual and stored generated columns.
+                        is_generated: matches!(c.hidden, 2 | 3),
+                        generation_expression: None,
+                        enum_name: None,
+                        enum_schema: None,
+                        comment: None,
+                        data_type: DataType {
+                            name,
+                            udt_name: c.declared_type.to_lowercase(),
+                            dimensions: 0,
+                            length,
+                            precision,
+                            scale,
+                            domains: Vec::new(),
+                        },
+                    }
+                })
+                .collect();
+
+            // SQLite foreign keys have no name, so they are grouped by their id instead.
+            let table_foreign_keys = foreign_keys_by_table
+                .get(table.name.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for foreign_key in table_foreign_keys {
+                let group: Vec<&&ForeignKeyMetadata> = table_foreign_keys
+                    .iter()
+                    .filter(|f| f.id == foreign_key.id)
+                    .collect();
+                let position = group
+                    .iter()
+                    .position(|f| f.column == foreign_key.column)
+                    .unwrap_or_default();
+
+                // Without explicit columns, a foreign key references the primary key.
+                let referenced_column = match &foreign_key.referenced_column {
+                    Some(column) => Some(column.clone()),
+                    None => {
+                        let mut primary_key: Vec<&ColumnMetadata> = columns_by_table
+                            .get(foreign_key.referenced_table.as_str())
+                            .into_iter()
+                            .flatten()
+                            .copied()
+                            .filter(|c| c.pk > 0)
+                            .collect();
+                        primary_key.sort_by_key(|c| c.pk);
+                        primary_key.get(position).map(|c| c.name.clone())
+                    }
+                };
+
+                let name = format!(
+                    "{}_{}_fkey",
+                    table.name,
+                    group
+                        .iter()
+                        .map(|f| f.column.as_str())
+                        .collect::<Vec<_>>()
+                        .join("_")
+                );
+
+                keys.push(KeyColumn {
+                    name,
+                    kind: ConstraintKind::ForeignKey,
+                    schema: schema.clone(),
+                    table: table.name.clone(),
+                    column: foreign_key.column.clone(),
+                    referenced: referenced_column.map(|column| Reference {
+                        schema: schema.clone(),
+                        table: foreign_key.referenced_table.clone(),
+                        column,
+                    }),
+                    on_delete: referential_action(&foreign_key.on_delete),
+                    on_update: referential_action(&foreign_key.on_update),
+                    deferrable: false,
+                    initially_deferred: false,
+                });
+            }
+
+            let table_index_columns = indexes_by_table
+                .get(table.name.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let mut table_indexes: Vec<TableIndex> = Vec::new();
+            for row in table_index_columns {
+                let position = table_indexes.iter().position(|i| i.index.name == row.name);
+                let index = match position {
+                    Some(position) => &mut table_indexes[position].index,
+                    None => {
+                        let (_, predicate) = parse_index(row.sql.as_deref());
+                        table_indexes.push(TableIndex {
+                            schema: schema.clone(),
+                            table: table.name.clone(),
+                            index: Index {
+                                name: row.name.clone(),
+                                keys: Vec::new(),
+                                is_unique: row.is_unique,
+                                is_primary: row.origin == "pk",
+                                method: "btree".to_string(),
+                                predicate,
+                            },
+                        });
+                        &mut table_indexes
+                            .last_mut()
+                            .expect("index was just pushed")
+                            .index
+                    }
+                };
+
+                match (&row.column, row.cid) {
+                    (Some(column), _) => index.keys.push(IndexKey::Column(column.clone())),
+                    // A cid of -2 marks an expression, which is only recorded in the index SQL.
+                    (None, -2) => {
+                        let (keys, _) = parse_index(row.sql.as_deref());
+                        if let Some(expression) = keys.get(index.keys.len()) {
+                            index.keys.push(IndexKey::Expression(expression.clone()));
+                        }
+                    }
+                    (None, _) => {}
+                }
+            }
+
+            // Unique constraints are backed by automatic indexes.
+            for index in table_indexes.iter().filter(|i| {
+                table_index_columns
+                    .iter()
+                    .any(|r| r.name == i.index.name && r.origin == "u")
+            }) {
+                keys.extend(index.index.columns().into_iter().map(|column| KeyColumn {
+                    name: index.index.name.clone(),
+                    kind: ConstraintKind::Unique,
+                    schema: schema.clone(),
+                    table: table.name.clone(),
+                    column: column.to_string(),
+                    referenced: None,
+                    on_delete: ReferentialAction::NoAction,
+                    on_update: ReferentialAction::NoAction,
+                    deferrable: false,
+                    initially_deferred: false,
+                }));
+            }
+            indexes.extend(table_indexes);
+
+            let (kind, definition) = match table.kind.as_str() {
+                "view" => (
+                    TableKind::View,
+                    table.sql.as_deref().and_then(view_definition),
+                ),
+                _ => (TableKind::Table, None),
+            };
+
+            tables.push(Table {
+                schema: schema.clone(),
+                name: table.name,
+                kind,
+                definition,
+                comment: None,
+                columns,
+                primary_key: Vec::new(),
+                foreign_keys: Vec::new(),
+                unique_constraints: Vec::new(),
+                indexes: Vec::new(),
+            });
+        }
+    }
+
+    catalog::assemble(&mut tables, &keys, &indexes);
+
+    Ok(Database {
+        tables,
+        enums: Vec::new(),
+    })
+}
+
+/// Split a declared type such as `VARCHAR(64)` or `DECIMAL(10, 2)` into its lowercase name,
+/// length, precision and scale.
+fn parse_type(declared_type: &str) -> (String, Option<i32>, Option<i32>, Option<i32>) {
+    let declared_type = declared_type.trim().to_lowercase();
+    let Some((name, arguments)) = declared_type.split_once('(') else {
+        return (declared_type, None, None, None);
+    };
+
+    let arguments: Vec<i32> = arguments
+        .trim_end_matches(')')
+        .split(',')
+        .filter_map(|a| a.trim().parse().ok())
+        .collect();
+
+    let name = name.trim().to_string();
+    match (name.as_str(), arguments.as_slice()) {
+        ("decimal" | "numeric", [precision]) => (name, None, Some(*precision), Some(0)),
+        ("decimal" | "numeric", [precision, scale]) => (name, None, Some(*precision), Some(*scale)),
+        (_, [length]) => (name, Some(*length), None, None),
+        _ => (name, None, None, None),
+    }
+}
+
+/// Extract the key list and the `WHERE` predicate of a `CREATE INDEX` statement, since SQLite
+/// only records expression keys and partial index predicates in the statement itself.
+fn parse_index(sql: Option<&str>) -> (Vec<String>, Option<String>) {
+    let Some(sql) = sql else {
+        return (Vec::new(), None);
+    };
+    let Some(start) = sql.find('(') else {
+        return (Vec::new(), None);
+    };
+
+    let mut keys = Vec::new();
+    let mut key = String::new();
+    let mut depth = 0;
+    let mut end = sql.len();
+    for (offset, c) in sql[start + 1..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                end = start + 1 + offset + 1;
+                break;
+            }
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                keys.push(key.trim().to_string());
+                key.clear();
+                continue;
+            }
+            _ => {}
+        }
+        key.push(c);
+    }
+    keys.push(key.trim().to_string());
+
+    let rest = sql[end..].trim();
+    let predicate = match rest.get(..5) {
+        Some(keyword) if keyword.eq_ignore_ascii_case("where") => {
+            Some(rest[5..].trim().to_string())
+        }
+        _ => None,
+    };
+
+    (keys, predicate)
+}
+
+/// Extract the `SELECT` statement from a `CREATE VIEW ... AS SELECT ...` statement, i.e.
+/// everything after the first `AS` outside of quotes and parentheses.
+fn view_definition(sql: &str) -> Option<String> {
+    let mut depth = 0;
+    for (offset, token) in tokenize(sql) {
+        match token {
+            Token::Symbol('(') => depth += 1,
+            Token::Symbol(')') => depth -= 1,
+            Token::Word(word) if depth == 0 && word.eq_ignore_ascii_case("as") => {
+                return Some(sql[offset + word.len()..].trim().to_string());
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// The columns of a `CREATE TABLE` statement that are declared `AUTOINCREMENT`.
+fn autoincrement_columns(sql: &str) -> Vec<String> {
+    let mut columns = Vec::new();
+    let mut definition: Vec<Token> = Vec::new();
+    let mut depth = 0;
+    for (_, token) in tokenize(sql) {
+        let is_end = match token {
+            Token::Symbol('(') => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+                false
+            }
+            Token::Symbol(')') => {
+                depth -= 1;
+                depth == 0
+            }
+            Token::Symbol(',') => depth == 1,
+            _ => false,
+        };
+
+        if !is_end {
+            if depth > 0 {
+                definition.push(token);
+            }
+            continue;
+        }
+
+        let is_autoincrement = definition
+            .iter()
+            .any(|t| matches!(t, Token::Word(word) if word.eq_ignore_ascii_case("autoincrement")));
+        if let (true, Some(Token::Word(name) | Token::Identifier(name))) =
+            (is_autoincrement, definition.first())
+        {
+            columns.push(name.clone());
+        }
+        definition.clear();
+
+        if depth == 0 {
+            break;
+        }
+    }
+
+    columns
+}
+
+/// A token of a SQL statement, as far as telling keywords apart from quoted text goes.
+#[derive(Debug)]
+enum Token {
+    /// A keyword or an unquoted identifier.
+    Word(String),
+    /// A quoted identifier, without its quotes.
+    Identifier(String),
+    /// A string literal.
+    String,
+    Symbol(char),
+}
+
+/// Split a statement into tokens along with their byte offsets, skipping whitespace and
+/// comments.
+fn tokenize(sql: &str) -> Vec<(usize, Token)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+
+    let mut tokens = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '-' if chars.next_if(|(_, c)| *c == '-').is_some() => {
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+            }
+            '/' if chars.next_if(|(_, c)| *c == '*').is_some() => {
+                let mut previous = ' ';
+                for (_, c) in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '\'' | '"' | '`' | '[' => {
+                let close = match c {
+                    '[' => ']',
+                    c => c,
+                };
+
+                let mut text = String::new();
+                while let Some((_, next)) = chars.next() {
+                    if next == close {
+                        // A doubled quote stands for the quote itself.
+                        if close == ']' || chars.next_if(|(_, c)| *c == close).is_none() {
+                            break;
+                        }
+                    }
+                    text.push(next);
+                }
+
+                tokens.push((
+                    offset,
+                    match c {
+                        '\'' => Token::String,
+                        _ => Token::Identifier(text),
+                    },
+                ));
+            }
+            c if is_word(c) => {
+                let mut word = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| is_word(*c)) {
+                    word.push(c);
+                }
+                tokens.push((offset, Token::Word(word)));
+            }
+            c => tokens.push((offset, Token::Symbol(c))),
+        }
+    }
+
+    tokens
+}
+
+/// Map a `pragma_foreign_key_list` action to its action.
+fn referential_action(action: &str) -> ReferentialAction {
+    match action {
+        "RESTRICT" => ReferentialAction::Restrict,
+        "CASCADE" => ReferentialAction::Cascade,
+        "SET NULL" => ReferentialAction::SetNull,
+        "SET DEFAULT" => ReferentialAction::SetDefault,
+        _ => ReferentialAction::NoAction,
+    }
+}
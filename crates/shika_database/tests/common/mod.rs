@@ -0,0 +1,41 @@
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use shika_database::{Database, IntrospectOptions, Introspector, SqlIntrospector};
+
+/// Write `sql` to a file named after `name` in the temporary directory. Every call gets a file
+/// of its own, since tests run in parallel.
+pub fn sql_file(name: &str, sql: &str) -> PathBuf {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "shika_{name}_{}_{}.sql",
+        std::process::id(),
+        CALLS.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&path, sql).unwrap();
+
+    path
+}
+
+/// Build a snapshot of the default schema from `sql`.
+pub async fn from_sql(name: &str, sql: &str) -> Database {
+    from_sql_in(name, sql, &[]).await
+}
+
+/// Build a snapshot of `schemas` from `sql`.
+pub async fn from_sql_in(name: &str, sql: &str, schemas: &[&str]) -> Database {
+    let path = sql_file(name, sql);
+    let options = IntrospectOptions {
+        schemas: schemas.iter().map(ToString::to_string).collect(),
+        exclude_tables: Vec::new(),
+    };
+
+    let result = SqlIntrospector::new(&path).introspect(&options).await;
+    std::fs::remove_file(&path).unwrap();
+
+    let mut database = result.unwrap();
+    database.sort();
+    database
+}
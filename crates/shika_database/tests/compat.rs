@@ -0,0 +1,111 @@
+//! The default severities of changes, between snapshots built from DDL.
+
+mod common;
+
+use common::from_sql;
+use shika_database::{Diff, Severity};
+
+/// The name and severity of every change from the `old` schema to the `new` one.
+async fn severities(old: &str, new: &str) -> Vec<(&'static str, Severity)> {
+    let old = from_sql("compat_old", old).await;
+    let new = from_sql("compat_new", new).await;
+
+    Diff::new(&old, &new)
+        .changes
+        .iter()
+        .map(|change| (change.name(), change.severity()))
+        .collect()
+}
+
+/// The severity of changing the type of a column from `from` to `to`.
+async fn type_change(from: &str, to: &str) -> Severity {
+    let changes = severities(
+        &format!("CREATE TABLE users (value {from});"),
+        &format!("CREATE TABLE users (value {to});"),
+    )
+    .await;
+    assert_eq!(changes.len(), 1, "{from} -> {to}");
+
+    changes[0].1
+}
+
+#[tokio::test]
+async fn widened_types_are_risky() {
+    for (from, to) in [
+        ("smallint", "integer"),
+        ("integer", "bigint"),
+        ("integer", "numeric"),
+        ("real", "double precision"),
+        ("varchar(32)", "varchar(64)"),
+        ("varchar(32)", "varchar"),
+        ("varchar(32)", "text"),
+        ("numeric(8,2)", "numeric(10,2)"),
+        ("numeric(8,2)", "numeric(9,3)"),
+        ("numeric(8,2)", "numeric"),
+        ("integer[]", "bigint[]"),
+    ] {
+        assert_eq!(
+            type_change(from, to).await,
+            Severity::Risky,
+            "{from} -> {to}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn other_type_changes_are_breaking() {
+    for (from, to) in [
+        ("bigint", "integer"),
+        ("integer", "text"),
+        ("text", "varchar(64)"),
+        ("varchar(64)", "varchar(32)"),
+        ("char(4)", "text"),
+        ("numeric", "numeric(10,2)"),
+        ("numeric(8,2)", "numeric(9,4)"),
+        ("double precision", "real"),
+        ("integer", "integer[]"),
+        ("timestamp", "timestamptz"),
+    ] {
+        assert_eq!(
+            type_change(from, to).await,
+            Severity::Breaking,
+            "{from} -> {to}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn classifies_columns_and_constraints() {
+    let changes = severities(
+        r#"
+            CREATE TYPE mood AS ENUM ('happy', 'sad');
+            CREATE TABLE users (id integer PRIMARY KEY, email text, legacy text, m mood);
+        "#,
+        r#"
+            CREATE TYPE mood AS ENUM ('happy', 'meh', 'sad');
+            CREATE TABLE users (
+                id integer PRIMARY KEY,
+                email text NOT NULL,
+                m mood,
+                created_at timestamptz NOT NULL DEFAULT now(),
+                name text NOT NULL
+            );
+            CREATE UNIQUE INDEX users_email_idx ON users (email);
+            CREATE TABLE teams (id integer);
+        "#,
+    )
+    .await;
+
+    assert_eq!(
+        changes,
+        [
+            ("enum_labels_changed", Severity::Risky),
+            ("table_added", Severity::Safe),
+            ("column_removed", Severity::Breaking),
+            ("column_nullability_changed", Severity::Breaking),
+            ("column_added", Severity::Safe),
+            ("column_added", Severity::Breaking),
+            ("index_added", Severity::Risky),
+        ]
+    );
+}
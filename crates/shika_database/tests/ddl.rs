@@ -0,0 +1,200 @@
+//! Snapshots built offline from DDL by `SqlIntrospector`.
+
+mod common;
+
+use common::{from_sql, from_sql_in, sql_file};
+use shika_database::{Database, IntrospectOptions, Introspector, SqlIntrospector, TableKind};
+
+#[tokio::test]
+async fn reads_pg_dump_forms() {
+    let database = from_sql(
+        "pg_dump",
+        r#"
+            CREATE UNLOGGED TABLE public.cache (id integer NOT NULL);
+            CREATE TABLE public.events (id integer NOT NULL, region text NOT NULL, n integer)
+                PARTITION BY LIST (region);
+            CREATE TABLE public.events_eu (id integer NOT NULL, region text NOT NULL, n integer);
+
+            ALTER TABLE public.events REPLICA IDENTITY FULL;
+            ALTER TABLE public.events SET (fillfactor=70);
+            ALTER TABLE ONLY public.events ATTACH PARTITION public.events_eu FOR VALUES IN ('eu');
+            ALTER TABLE ONLY public.cache ADD CONSTRAINT cache_pkey PRIMARY KEY (id);
+            ALTER TABLE public.cache CLUSTER ON cache_pkey;
+            ALTER TABLE public.events ADD CONSTRAINT events_n_check CHECK (n > 0) NOT VALID;
+            ALTER TABLE public.events ADD CONSTRAINT events_n_fkey
+                FOREIGN KEY (n) REFERENCES public.cache(id) NOT VALID;
+
+            CREATE MATERIALIZED VIEW public.totals AS SELECT id, n FROM public.events WITH NO DATA;
+            CREATE MATERIALIZED VIEW public.stale AS SELECT id FROM public.events WITH DATA;
+            DROP MATERIALIZED VIEW public.stale;
+        "#,
+    )
+    .await;
+
+    let names: Vec<&str> = database.tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["cache", "events", "events_eu", "totals"]);
+
+    assert_eq!(database.tables[0].primary_key, ["id"]);
+    assert_eq!(database.tables[1].foreign_keys[0].name, "events_n_fkey");
+
+    let totals = &database.tables[3];
+    assert_eq!(totals.kind, TableKind::MaterializedView);
+    assert_eq!(totals.columns.len(), 2);
+}
+
+#[tokio::test]
+async fn strips_identity_options_after_non_ascii_text() {
+    let database = from_sql(
+        "identity",
+        r#"
+            CREATE TABLE public.files (
+                name text DEFAULT 'ﬁﬁﬁﬁ ıı',
+                id integer GENERATED ALWAYS AS IDENTITY (SEQUENCE NAME public.files_id_seq START WITH 1)
+            );
+        "#,
+    )
+    .await;
+
+    let columns = &database.tables[0].columns;
+    assert_eq!(columns[0].default.as_deref(), Some("'ﬁﬁﬁﬁ ıı'"));
+    assert_eq!(
+        columns[1].identity_generation,
+        Some(shika_database::IdentityGeneration::Always)
+    );
+}
+
+#[tokio::test]
+async fn qualifies_serial_sequences_outside_the_default_schema() {
+    let database = from_sql_in(
+        "serial",
+        "CREATE TABLE public.users (id serial); CREATE TABLE app.users (id bigserial);",
+        &["public", "app"],
+    )
+    .await;
+
+    let defaults: Vec<_> = database
+        .tables
+        .iter()
+        .map(|t| t.columns[0].default.as_deref().unwrap())
+        .collect();
+    assert_eq!(
+        defaults,
+        [
+            "nextval('app.users_id_seq'::regclass)",
+            "nextval('users_id_seq'::regclass)"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn skips_statements_that_cannot_be_parsed() {
+    let path = sql_file(
+        "unparseable",
+        "CREATE TABLE public.before (id integer);\n\
+         CREATE TABLE public.broken (id integer) NOT A TABLE OPTION;\n\
+         CREATE TABLE public.after (id integer);\n",
+    );
+    let result = SqlIntrospector::new(&path)
+        .introspect_with_skipped(&IntrospectOptions::default())
+        .await;
+    std::fs::remove_file(&path).unwrap();
+
+    let (mut database, skipped) = result.unwrap();
+    database.sort();
+    let names: Vec<&str> = database.tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["after", "before"]);
+
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0].file, path);
+    assert_eq!(skipped[0].line, 2);
+}
+
+#[tokio::test]
+async fn reads_domains() {
+    let database = from_sql(
+        "domains",
+        r#"
+            CREATE DOMAIN public.email AS text NOT NULL CHECK (VALUE ~ '@');
+            CREATE DOMAIN work_email public.email;
+            CREATE DOMAIN tags AS varchar(20)[] CHECK (VALUE IS NOT NULL);
+            CREATE DOMAIN dropped AS integer;
+            DROP DOMAIN IF EXISTS dropped, missing CASCADE;
+            CREATE TABLE public.users (
+                email email,
+                work work_email NULL,
+                tags tags,
+                old dropped
+            );
+        "#,
+    )
+    .await;
+
+    let columns = &database.tables[0].columns;
+    let summary: Vec<_> = columns
+        .iter()
+        .map(|c| {
+            (
+                c.kind.as_str(),
+                c.required,
+                c.data_type.name.as_str(),
+                c.data_type.domains.join(", "),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("text", true, "text", "email".to_string()),
+            (
+                "USER-DEFINED",
+                false,
+                "text",
+                "work_email, email".to_string()
+            ),
+            ("ARRAY", false, "varchar", "tags".to_string()),
+            ("dropped", false, "dropped", String::new()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn keeps_tables_of_the_same_name_apart() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let options = IntrospectOptions {
+        schemas: vec!["billing".to_string(), "auth".to_string()],
+        exclude_tables: Vec::new(),
+    };
+    let mut database = SqlIntrospector::new(fixtures.join("same_name_tables.sql"))
+        .introspect(&options)
+        .await
+        .unwrap();
+    database.sort();
+
+    let accounts: Vec<_> = database
+        .tables
+        .iter()
+        .filter(|t| t.name == "accounts")
+        .collect();
+    let columns = |i: usize| -> Vec<&str> {
+        accounts[i]
+            .columns
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect()
+    };
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].schema, "auth");
+    assert_eq!(columns(0), ["id", "user_id", "email"]);
+    assert_eq!(accounts[0].foreign_keys[0].referenced_table, "users");
+    assert_eq!(accounts[1].schema, "billing");
+    assert_eq!(columns(1), ["id", "customer_id", "balance"]);
+    assert_eq!(accounts[1].foreign_keys[0].referenced_table, "customers");
+
+    let expected = Database::load(fixtures.join("same_name_tables.yaml"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        database.fingerprint().unwrap(),
+        expected.fingerprint().unwrap()
+    );
+}
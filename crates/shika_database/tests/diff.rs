@@ -0,0 +1,86 @@
+//! The text and JSON output of `shika diff`, between snapshots built from DDL.
+
+mod common;
+
+use common::from_sql;
+use shika_database::Diff;
+
+const OLD: &str = r#"
+    CREATE TYPE mood AS ENUM ('happy', 'sad');
+    CREATE TABLE orgs (id integer PRIMARY KEY, name text);
+    CREATE TABLE users (
+        id integer PRIMARY KEY,
+        org_id integer,
+        email text,
+        legacy text
+    );
+"#;
+
+const NEW: &str = r#"
+    CREATE TYPE mood AS ENUM ('happy', 'meh', 'sad');
+    CREATE TABLE orgs (id integer PRIMARY KEY, name text NOT NULL);
+    CREATE TABLE users (
+        id bigint PRIMARY KEY,
+        org_id integer REFERENCES orgs (id),
+        email text DEFAULT '',
+        mood mood
+    );
+    CREATE UNIQUE INDEX users_email_idx ON users (lower(email));
+    CREATE TABLE teams (id integer);
+"#;
+
+async fn diff(old: &str, new: &str) -> Diff {
+    let old = from_sql("diff_old", old).await;
+    let new = from_sql("diff_new", new).await;
+
+    Diff::new(&old, &new)
+}
+
+#[tokio::test]
+async fn prints_a_line_per_change() {
+    // Changes follow the tables in order, with removals and changes before additions.
+    let diff = diff(OLD, NEW).await;
+
+    assert_eq!(
+        diff.to_string(),
+        "~ enum public.mood labels (happy, sad) -> (happy, meh, sad)\n\
+         ~ column public.orgs.name nullable -> not null\n\
+         + table public.teams\n\
+         - column public.users.legacy\n\
+         ~ column public.users.id type int4 -> int8\n\
+         ~ column public.users.email default none -> ''\n\
+         + column public.users.mood mood\n\
+         + foreign key public.users.users_org_id_fkey (org_id) -> public.orgs (id)\n\
+         + unique index public.users.users_email_idx (lower(email))\n"
+    );
+}
+
+#[tokio::test]
+async fn serializes_changes_tagged_by_kind() {
+    let diff = diff(
+        "CREATE TABLE users (id integer);",
+        "CREATE TABLE users (id integer NOT NULL);",
+    )
+    .await;
+
+    assert_eq!(
+        serde_json::to_value(&diff).unwrap(),
+        serde_json::json!({
+            "changes": [{
+                "change": "column_nullability_changed",
+                "schema": "public",
+                "table": "users",
+                "column": "id",
+                "required": true,
+            }]
+        })
+    );
+}
+
+#[tokio::test]
+async fn finds_nothing_between_equal_snapshots() {
+    let diff = diff(OLD, OLD).await;
+
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "");
+}
@@ -0,0 +1,306 @@
+//! The up and down SQL of every kind of change, between snapshots built from DDL.
+
+mod common;
+
+use common::{from_sql, from_sql_in};
+use shika_database::Diff;
+
+/// The up and down migration from the `old` schema to the `new` one.
+async fn migrate(old: &str, new: &str) -> (String, String) {
+    let old = from_sql("migration_old", old).await;
+    let new = from_sql("migration_new", new).await;
+
+    let diff = Diff::new(&old, &new);
+    (diff.to_sql(), diff.inverse().to_sql())
+}
+
+#[tokio::test]
+async fn enums() {
+    let (up, down) = migrate("", "CREATE TYPE mood AS ENUM ('happy', 'sad');").await;
+    assert_eq!(
+        up,
+        "CREATE TYPE \"public\".\"mood\" AS ENUM ('happy', 'sad');\n"
+    );
+    assert_eq!(down, "DROP TYPE \"public\".\"mood\";\n");
+}
+
+#[tokio::test]
+async fn enum_labels() {
+    let (up, down) = migrate(
+        "CREATE TYPE mood AS ENUM ('happy', 'sad');",
+        "CREATE TYPE mood AS ENUM ('happy', 'meh', 'sad');",
+    )
+    .await;
+    assert_eq!(
+        up,
+        "ALTER TYPE \"public\".\"mood\" ADD VALUE 'meh' AFTER 'happy';\n"
+    );
+    assert_eq!(
+        down,
+        "-- The label 'meh' can't be removed from \"public\".\"mood\" without recreating the type.\n"
+    );
+}
+
+#[tokio::test]
+async fn enum_labels_before_the_first_existing_one() {
+    let (up, _) = migrate(
+        "CREATE TYPE mood AS ENUM ('c');",
+        "CREATE TYPE mood AS ENUM ('a', 'b', 'c', 'd');",
+    )
+    .await;
+
+    // `b` has to exist before `a` can be placed before it.
+    assert_eq!(
+        up,
+        "ALTER TYPE \"public\".\"mood\" ADD VALUE 'b' BEFORE 'c';\n\
+         ALTER TYPE \"public\".\"mood\" ADD VALUE 'a' BEFORE 'b';\n\
+         ALTER TYPE \"public\".\"mood\" ADD VALUE 'd' AFTER 'c';\n"
+    );
+}
+
+#[tokio::test]
+async fn tables() {
+    let (up, down) = migrate(
+        "",
+        "CREATE TABLE users (id integer CONSTRAINT users_pk PRIMARY KEY, email text NOT NULL);",
+    )
+    .await;
+    assert_eq!(
+        up,
+        "CREATE TABLE \"public\".\"users\" (\n    \"id\" int4 NOT NULL,\n    \"email\" text NOT NULL,\n    CONSTRAINT \"users_pk\" PRIMARY KEY (\"id\")\n);\n"
+    );
+    assert_eq!(down, "DROP TABLE \"public\".\"users\";\n");
+}
+
+#[tokio::test]
+async fn columns() {
+    let (up, down) = migrate(
+        "CREATE TABLE users (id integer);",
+        "CREATE TABLE users (id integer, email varchar(64) NOT NULL DEFAULT '');",
+    )
+    .await;
+    assert_eq!(
+        up,
+        "ALTER TABLE \"public\".\"users\" ADD COLUMN \"email\" varchar(64) NOT NULL DEFAULT '';\n"
+    );
+    assert_eq!(
+        down,
+        "ALTER TABLE \"public\".\"users\" DROP COLUMN \"email\";\n"
+    );
+}
+
+#[tokio::test]
+async fn column_types() {
+    let (up, down) = migrate(
+        "CREATE TABLE users (id integer);",
+        "CREATE TABLE users (id bigint);",
+    )
+    .await;
+    assert_eq!(
+        up,
+        "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"id\" TYPE int8 USING \"id\"::int8;\n"
+    );
+    assert_eq!(
+        down,
+        "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"id\" TYPE int4 USING \"id\"::int4;\n"
+    );
+}
+
+#[tokio::test]
+async fn column_nullability() {
+    let (up, down) = migrate(
+        "CREATE TABLE users (id integer);",
+        "CREATE TABLE users (id integer NOT NULL);",
+    )
+    .await;
+    assert_eq!(
+        up,
+        "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"id\" SET NOT NULL;\n"
+    );
+    assert_eq!(
+        down,
+        "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"id\" DROP NOT NULL;\n"
+    );
+}
+
+#[tokio::test]
+async fn column_defaults() {
+    let (up, down) = migrate(
+        "CREATE TABLE users (id integer);",
+        "CREATE TABLE users (id integer DEFAULT 1);",
+    )
+    .await;
+    assert_eq!(
+        up,
+        "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"id\" SET DEFAULT 1;\n"
+    );
+    assert_eq!(
+        down,
+        "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"id\" DROP DEFAULT;\n"
+    );
+}
+
+#[tokio::test]
+async fn primary_keys_use_the_name_of_their_index() {
+    let (up, down) = migrate(
+        "CREATE TABLE users (id integer NOT NULL, CONSTRAINT users_pk PRIMARY KEY (id));",
+        "CREATE TABLE users (id integer NOT NULL, tenant integer NOT NULL, \
+            CONSTRAINT users_tenant_pk PRIMARY KEY (tenant, id));",
+    )
+    .await;
+    assert_eq!(
+        up,
+        "ALTER TABLE \"public\".\"users\" DROP CONSTRAINT \"users_pk\";\n\
+         ALTER TABLE \"public\".\"users\" ADD COLUMN \"tenant\" int4 NOT NULL;\n\
+         ALTER TABLE \"public\".\"users\" ADD CONSTRAINT \"users_tenant_pk\" PRIMARY KEY (\"tenant\", \"id\");\n"
+    );
+    assert_eq!(
+        down,
+        "ALTER TABLE \"public\".\"users\" DROP CONSTRAINT \"users_tenant_pk\";\n\
+         ALTER TABLE \"public\".\"users\" DROP COLUMN \"tenant\";\n\
+         ALTER TABLE \"public\".\"users\" ADD CONSTRAINT \"users_pk\" PRIMARY KEY (\"id\");\n"
+    );
+}
+
+#[tokio::test]
+async fn foreign_keys() {
+    let (up, down) = migrate(
+        "CREATE TABLE orgs (id integer PRIMARY KEY); CREATE TABLE users (org_id integer);",
+        "CREATE TABLE orgs (id integer PRIMARY KEY); \
+            CREATE TABLE users (org_id integer REFERENCES orgs (id) ON DELETE CASCADE);",
+    )
+    .await;
+    assert_eq!(
+        up,
+        "ALTER TABLE \"public\".\"users\" ADD CONSTRAINT \"users_org_id_fkey\" FOREIGN KEY (\"org_id\") REFERENCES \"public\".\"orgs\" (\"id\") ON DELETE CASCADE;\n"
+    );
+    assert_eq!(
+        down,
+        "ALTER TABLE \"public\".\"users\" DROP CONSTRAINT \"users_org_id_fkey\";\n"
+    );
+}
+
+#[tokio::test]
+async fn unique_constraints() {
+    let (up, down) = migrate(
+        "CREATE TABLE users (a integer, b integer);",
+        "CREATE TABLE users (a integer, b integer, CONSTRAINT users_a_b_key UNIQUE (a, b));",
+    )
+    .await;
+    assert_eq!(
+        up,
+        "ALTER TABLE \"public\".\"users\" ADD CONSTRAINT \"users_a_b_key\" UNIQUE (\"a\", \"b\");\n"
+    );
+    assert_eq!(
+        down,
+        "ALTER TABLE \"public\".\"users\" DROP CONSTRAINT \"users_a_b_key\";\n"
+    );
+}
+
+#[tokio::test]
+async fn indexes_keep_their_key_order() {
+    let (up, down) = migrate(
+        "CREATE TABLE users (email text, tenant integer);",
+        "CREATE TABLE users (email text, tenant integer); \
+            CREATE UNIQUE INDEX users_email_idx ON users (lower(email), tenant) WHERE tenant > 0;",
+    )
+    .await;
+    assert_eq!(
+        up,
+        "CREATE UNIQUE INDEX \"users_email_idx\" ON \"public\".\"users\" USING btree ((lower(email)), \"tenant\") WHERE tenant > 0;\n"
+    );
+    assert_eq!(down, "DROP INDEX \"public\".\"users_email_idx\";\n");
+}
+
+#[tokio::test]
+async fn orders_statements_by_dependency() {
+    let (up, down) = migrate(
+        "CREATE TABLE orgs (id integer NOT NULL, region text NOT NULL, PRIMARY KEY (id, region));",
+        "CREATE TABLE orgs (id integer NOT NULL, code text, PRIMARY KEY (id)); \
+            CREATE VIEW org_codes AS SELECT id, code FROM orgs;",
+    )
+    .await;
+
+    // The primary key goes before the column in it, and the view after the column it selects.
+    assert_eq!(
+        up,
+        "ALTER TABLE \"public\".\"orgs\" DROP CONSTRAINT \"orgs_pkey\";\n\
+         ALTER TABLE \"public\".\"orgs\" DROP COLUMN \"region\";\n\
+         ALTER TABLE \"public\".\"orgs\" ADD COLUMN \"code\" text;\n\
+         CREATE VIEW \"public\".\"org_codes\" AS SELECT id, code FROM orgs;\n\
+         ALTER TABLE \"public\".\"orgs\" ADD CONSTRAINT \"orgs_pkey\" PRIMARY KEY (\"id\");\n"
+    );
+    assert_eq!(
+        down,
+        "DROP VIEW \"public\".\"org_codes\";\n\
+         ALTER TABLE \"public\".\"orgs\" DROP CONSTRAINT \"orgs_pkey\";\n\
+         ALTER TABLE \"public\".\"orgs\" DROP COLUMN \"code\";\n\
+         ALTER TABLE \"public\".\"orgs\" ADD COLUMN \"region\" text NOT NULL;\n\
+         ALTER TABLE \"public\".\"orgs\" ADD CONSTRAINT \"orgs_pkey\" PRIMARY KEY (\"id\", \"region\");\n"
+    );
+}
+
+#[tokio::test]
+async fn orders_views_by_what_they_select_from() {
+    let (up, down) = migrate(
+        "CREATE TABLE users (id integer);",
+        "CREATE TABLE users (id integer); \
+            CREATE VIEW a_top AS SELECT id FROM z_base; \
+            CREATE VIEW z_base AS SELECT id FROM users;",
+    )
+    .await;
+
+    assert_eq!(
+        up,
+        "CREATE VIEW \"public\".\"z_base\" AS SELECT id FROM users;\n\
+         CREATE VIEW \"public\".\"a_top\" AS SELECT id FROM z_base;\n"
+    );
+    assert_eq!(
+        down,
+        "DROP VIEW \"public\".\"a_top\";\n\
+         DROP VIEW \"public\".\"z_base\";\n"
+    );
+}
+
+#[tokio::test]
+async fn creates_the_schemas_of_new_tables() {
+    let new = from_sql_in(
+        "schemas_new",
+        "CREATE TABLE billing.invoices (id integer);",
+        &["billing"],
+    )
+    .await;
+
+    let diff = Diff::new(&from_sql("schemas_old", "").await, &new);
+    let (up, down) = (diff.to_sql(), diff.inverse().to_sql());
+    assert_eq!(
+        up,
+        "CREATE SCHEMA IF NOT EXISTS \"billing\";\n\
+         CREATE TABLE \"billing\".\"invoices\" (\n    \"id\" int4\n);\n"
+    );
+    assert_eq!(down, "DROP TABLE \"billing\".\"invoices\";\n");
+}
+
+#[tokio::test]
+async fn splits_off_enum_labels() {
+    let old = from_sql(
+        "labels_old",
+        "CREATE TYPE mood AS ENUM ('happy'); CREATE TABLE users (m mood);",
+    )
+    .await;
+    let new = from_sql(
+        "labels_new",
+        "CREATE TYPE mood AS ENUM ('happy', 'sad'); CREATE TABLE users (m mood DEFAULT 'sad');",
+    )
+    .await;
+
+    let (labels, rest) = Diff::new(&old, &new).split_enum_labels();
+    assert_eq!(
+        labels.to_sql(),
+        "ALTER TYPE \"public\".\"mood\" ADD VALUE 'sad' AFTER 'happy';\n"
+    );
+    assert_eq!(
+        rest.to_sql(),
+        "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"m\" SET DEFAULT 'sad';\n"
+    );
+}
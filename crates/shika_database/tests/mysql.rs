@@ -0,0 +1,124 @@
+//! Introspection of `fixtures/mysql.sql` on a live MySQL or MariaDB server.
+//!
+//! The server is taken from `SHIKA_TEST_MYSQL_URL`, e.g. `mysql://root@localhost:3306`, and
+//! every test is skipped when it isn't set. Each test loads the fixture into a database of its
+//! own and drops it again.
+
+use shika_database::{
+    Database, IndexKey, IntrospectOptions, Introspector, MySqlIntrospector, ReferentialAction,
+};
+use sqlx::{Executor, MySql, Pool};
+
+const FIXTURE: &str = include_str!("../fixtures/mysql.sql");
+
+/// Load the fixture into a fresh database named `name` and introspect it, or return `None`
+/// when no server is configured.
+async fn introspect(name: &str, exclude_tables: &[&str]) -> Option<Database> {
+    let Ok(url) = std::env::var("SHIKA_TEST_MYSQL_URL") else {
+        eprintln!("SHIKA_TEST_MYSQL_URL is not set, skipping");
+        return None;
+    };
+    let url = url.trim_end_matches('/');
+
+    let server = Pool::<MySql>::connect(url).await.unwrap();
+    server
+        .execute(format!("DROP DATABASE IF EXISTS `{name}`").as_str())
+        .await
+        .unwrap();
+    server
+        .execute(format!("CREATE DATABASE `{name}`").as_str())
+        .await
+        .unwrap();
+
+    let database_url = format!("{url}/{name}");
+    let connection = Pool::<MySql>::connect(&database_url).await.unwrap();
+    for statement in FIXTURE.split(';').filter(|s| {
+        s.lines()
+            .any(|line| !line.trim().is_empty() && !line.trim().starts_with("--"))
+    }) {
+        connection.execute(statement).await.unwrap();
+    }
+    connection.close().await;
+
+    let options = IntrospectOptions {
+        schemas: Vec::new(),
+        exclude_tables: exclude_tables.iter().map(ToString::to_string).collect(),
+    };
+    let result = MySqlIntrospector::new(&database_url)
+        .introspect(&options)
+        .await;
+
+    server
+        .execute(format!("DROP DATABASE `{name}`").as_str())
+        .await
+        .unwrap();
+
+    let mut database = result.unwrap();
+    database.sort();
+    Some(database)
+}
+
+#[tokio::test]
+async fn introspects_keys_enums_and_indexes() {
+    let Some(database) = introspect("shika_test_fixture", &[]).await else {
+        return;
+    };
+
+    let names: Vec<&str> = database.tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["member_names", "members", "organizations"]);
+
+    let organizations = &database.tables[2];
+    assert_eq!(organizations.primary_key, ["id", "region"]);
+    assert_eq!(
+        organizations.comment.as_deref(),
+        Some("Organizations owning members")
+    );
+
+    let members = &database.tables[1];
+    let foreign_key = &members.foreign_keys[0];
+    assert_eq!(foreign_key.name, "members_organization_fkey");
+    assert_eq!(foreign_key.referenced_table, "organizations");
+    assert_eq!(foreign_key.columns.len(), 2);
+    assert_eq!(foreign_key.on_delete, ReferentialAction::Cascade);
+
+    let mood = members.columns.iter().find(|c| c.name == "mood").unwrap();
+    assert_eq!(mood.enum_name.as_deref(), Some("members_mood"));
+    assert_eq!(database.enums.len(), 1);
+    assert_eq!(
+        database.enums[0].labels,
+        ["happy", "sad", "it's complicated"]
+    );
+
+    let full_name = members
+        .columns
+        .iter()
+        .find(|c| c.name == "full_name")
+        .unwrap();
+    assert!(full_name.is_generated);
+
+    let name_key = members
+        .indexes
+        .iter()
+        .find(|i| i.name == "members_name_key")
+        .unwrap();
+    assert!(name_key.is_unique);
+    assert_eq!(
+        name_key.keys,
+        [
+            IndexKey::Column("first_name".to_string()),
+            IndexKey::Column("last_name".to_string())
+        ]
+    );
+}
+
+#[tokio::test]
+async fn leaves_out_enums_of_excluded_tables() {
+    let Some(database) = introspect("shika_test_excluded", &["members", "member_names"]).await
+    else {
+        return;
+    };
+
+    let names: Vec<&str> = database.tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["organizations"]);
+    assert!(database.enums.is_empty());
+}
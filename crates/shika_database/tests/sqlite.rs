@@ -0,0 +1,139 @@
+//! Introspection of a temporary SQLite database built from `fixtures/sqlite.sql`.
+
+use shika_database::{
+    Column, Database, IdentityGeneration, IndexKey, IntrospectOptions, Introspector,
+    ReferentialAction, SqliteIntrospector, Table,
+};
+use sqlx::{
+    Pool, Sqlite,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+};
+
+const FIXTURE: &str = include_str!("../fixtures/sqlite.sql");
+
+/// Build a database file named `name` in the temporary directory from the fixture followed by
+/// `extra`, and introspect it.
+async fn introspect(name: &str, extra: &str) -> Database {
+    let path = std::env::temp_dir().join(format!("shika_{name}_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let connection: Pool<Sqlite> = SqlitePoolOptions::new()
+        .connect_with(
+            SqliteConnectOptions::new()
+                .filename(&path)
+                .create_if_missing(true),
+        )
+        .await
+        .unwrap();
+    sqlx::raw_sql(FIXTURE).execute(&connection).await.unwrap();
+    sqlx::raw_sql(extra).execute(&connection).await.unwrap();
+    connection.close().await;
+
+    let result = SqliteIntrospector::new(format!("sqlite://{}", path.display()))
+        .introspect(&IntrospectOptions::default())
+        .await;
+    std::fs::remove_file(&path).unwrap();
+
+    let mut database = result.unwrap();
+    database.sort();
+    database
+}
+
+fn table<'a>(database: &'a Database, name: &str) -> &'a Table {
+    database.tables.iter().find(|t| t.name == name).unwrap()
+}
+
+fn column<'a>(table: &'a Table, name: &str) -> &'a Column {
+    table.columns.iter().find(|c| c.name == name).unwrap()
+}
+
+#[tokio::test]
+async fn introspects_the_fixture() {
+    let database = introspect("fixture", "").await;
+
+    let names: Vec<&str> = database.tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["invitations", "member_names", "members", "organizations"]
+    );
+    assert!(database.tables.iter().all(|t| t.schema == "main"));
+
+    let organizations = table(&database, "organizations");
+    assert_eq!(organizations.primary_key, ["id", "region"]);
+
+    let members = table(&database, "members");
+    let foreign_key = &members.foreign_keys[0];
+    assert_eq!(
+        foreign_key.name,
+        "members_organization_id_organization_region_fkey"
+    );
+    assert_eq!(foreign_key.referenced_table, "organizations");
+    assert_eq!(foreign_key.on_delete, ReferentialAction::Cascade);
+
+    let unique = &members.unique_constraints[0];
+    assert_eq!(unique.columns, ["first_name", "last_name"]);
+
+    let email_key = members
+        .indexes
+        .iter()
+        .find(|i| i.name == "members_email_key")
+        .unwrap();
+    assert!(email_key.is_unique);
+    assert_eq!(
+        email_key.keys,
+        [IndexKey::Expression("lower(email)".to_string())]
+    );
+    assert_eq!(email_key.predicate.as_deref(), Some("email IS NOT NULL"));
+
+    assert!(column(members, "full_name").is_generated);
+
+    // A foreign key without columns references the primary key.
+    let invitations = table(&database, "invitations");
+    let references = column(invitations, "member_id")
+        .references
+        .as_ref()
+        .unwrap();
+    assert_eq!(
+        (references.table.as_str(), references.column.as_str()),
+        ("members", "id")
+    );
+
+    let member_names = table(&database, "member_names");
+    assert_eq!(
+        member_names.definition.as_deref(),
+        Some("SELECT id, full_name FROM members")
+    );
+}
+
+#[tokio::test]
+async fn tells_autoincrement_apart_per_column() {
+    let database = introspect(
+        "autoincrement",
+        r#"
+            CREATE TABLE notes (
+                id INTEGER PRIMARY KEY,
+                "autoincrement" TEXT DEFAULT 'AUTOINCREMENT'
+            );
+        "#,
+    )
+    .await;
+
+    let id = |name: &str| column(table(&database, name), "id").identity_generation;
+    assert_eq!(id("members"), Some(IdentityGeneration::Always));
+    assert_eq!(id("invitations"), Some(IdentityGeneration::ByDefault));
+    assert_eq!(id("notes"), Some(IdentityGeneration::ByDefault));
+}
+
+#[tokio::test]
+async fn finds_the_select_of_a_view_across_whitespace() {
+    let database = introspect(
+        "views",
+        "CREATE VIEW \"cast as text\"\nAS\n\tSELECT CAST(id AS TEXT) AS id FROM members;",
+    )
+    .await;
+
+    assert_eq!(
+        table(&database, "cast as text").definition.as_deref(),
+        Some("SELECT CAST(id AS TEXT) AS id FROM members")
+    );
+}
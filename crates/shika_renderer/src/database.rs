@@ -1,46 +1,140 @@
 use serde::Serialize;
+use shika_database::{DataType, IdentityGeneration, IndexKey, ReferentialAction, TableKind};
+
+use crate::types::TypeMap;
 
 #[derive(Serialize)]
 pub struct Database {
     pub tables: Vec<Table>,
+    pub enums: Vec<Enum>,
+    pub schemas: Vec<Schema>,
 }
 
+/// The tables and enums of a single schema, so templates can emit one module per schema.
 #[derive(Serialize)]
+pub struct Schema {
+    pub name: String,
+    pub tables: Vec<Table>,
+    pub enums: Vec<Enum>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Enum {
+    pub schema: String,
+    pub name: String,
+    pub labels: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
 pub struct Table {
+    pub schema: String,
     pub name: String,
+    pub kind: TableKind,
+    pub definition: Option<String>,
+    pub comment: Option<String>,
     pub columns: Vec<Column>,
+    pub primary_key: Vec<String>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub unique_constraints: Vec<UniqueConstraint>,
+    pub indexes: Vec<Index>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct Column {
     pub name: String,
+    /// The type in the target language, e.g. `Option<i32>`.
     pub kind: String,
+    /// The type in the target language without the nullable wrapper, e.g. `i32`.
+    pub base_type: String,
+    /// The type as written in DDL, e.g. `int4` or `varchar(64)`.
+    pub sql_type: String,
+    /// The most characters a `varchar(n)` or `char(n)` column holds, for templates to emit
+    /// validation with, e.g. `64` for `varchar(64)`.
+    pub max_length: Option<i32>,
     pub required: bool,
     pub referenced_by: Vec<Reference>,
     pub references: Option<Reference>,
     pub is_primary_key: bool,
     pub is_unique: bool,
+    pub default: Option<String>,
+    pub is_identity: bool,
+    pub identity_generation: Option<IdentityGeneration>,
+    pub is_generated: bool,
+    pub generation_expression: Option<String>,
+    pub enum_name: Option<String>,
+    pub enum_schema: Option<String>,
+    pub comment: Option<String>,
+    pub data_type: DataType,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ForeignKey {
+    pub name: String,
+    pub referenced_schema: String,
+    pub referenced_table: String,
+    pub columns: Vec<ForeignKeyColumn>,
+    pub on_delete: ReferentialAction,
+    pub on_update: ReferentialAction,
+    pub deferrable: bool,
+    pub initially_deferred: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ForeignKeyColumn {
+    pub column: String,
+    pub referenced_column: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UniqueConstraint {
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Index {
+    pub name: String,
+    /// Every key in key order, columns as their name and expressions as written.
+    pub keys: Vec<String>,
+    pub columns: Vec<String>,
+    pub expressions: Vec<String>,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    pub method: String,
+    pub predicate: Option<String>,
 }
 
 #[derive(Serialize, Clone, Debug)]
 pub struct Reference {
+    schema: String,
     table: String,
     column: String,
 }
 
-impl From<shika_database::Database> for Database {
-    fn from(db: shika_database::Database) -> Self {
-        let tables = db
+impl Database {
+    /// Prepare a snapshot for rendering, with column types resolved through the type map.
+    pub fn new(db: shika_database::Database, type_map: &TypeMap) -> Self {
+        let tables: Vec<Table> = db
             .tables
             .into_iter()
             .map(|t| Table {
-                name: t.name,
+                schema: t.schema.clone(),
+                name: t.name.clone(),
+                kind: t.kind,
+                definition: t.definition,
+                comment: t.comment,
                 columns: t
                     .columns
                     .into_iter()
                     .map(|c| Column {
+                        kind: type_map.kind(&t.schema, &t.name, &c),
+                        base_type: type_map.base_type(&t.schema, &t.name, &c),
+                        sql_type: c.sql_type(),
+                        max_length: match c.data_type.name.as_str() {
+                            "varchar" | "bpchar" | "char" => c.data_type.length,
+                            _ => None,
+                        },
                         name: c.name,
-                        kind: from_kind(&c.kind, c.required),
                         is_primary_key: c.is_primary_key,
                         is_unique: c.is_unique,
                         required: c.required,
@@ -48,51 +142,121 @@ impl From<shika_database::Database> for Database {
                             .referenced_by
                             .into_iter()
                             .map(|r| Reference {
+                                schema: r.schema,
                                 table: r.table,
                                 column: r.column,
                             })
                             .collect(),
                         references: c.references.map(|r| Reference {
+                            schema: r.schema,
                             table: r.table,
                             column: r.column,
                         }),
+                        default: c.default,
+                        is_identity: c.is_identity,
+                        identity_generation: c.identity_generation,
+                        is_generated: c.is_generated,
+                        generation_expression: c.generation_expression,
+                        enum_name: c.enum_name,
+                        enum_schema: c.enum_schema,
+                        data_type: c.data_type,
+                        comment: c.comment,
+                    })
+                    .collect(),
+                primary_key: t.primary_key,
+                foreign_keys: t
+                    .foreign_keys
+                    .into_iter()
+                    .map(|fk| ForeignKey {
+                        name: fk.name,
+                        referenced_schema: fk.referenced_schema,
+                        referenced_table: fk.referenced_table,
+                        columns: fk
+                            .columns
+                            .into_iter()
+                            .map(|c| ForeignKeyColumn {
+                                column: c.column,
+                                referenced_column: c.referenced_column,
+                            })
+                            .collect(),
+                        on_delete: fk.on_delete,
+                        on_update: fk.on_update,
+                        deferrable: fk.deferrable,
+                        initially_deferred: fk.initially_deferred,
+                    })
+                    .collect(),
+                unique_constraints: t
+                    .unique_constraints
+                    .into_iter()
+                    .map(|u| UniqueConstraint {
+                        name: u.name,
+                        columns: u.columns,
+                    })
+                    .collect(),
+                indexes: t
+                    .indexes
+                    .into_iter()
+                    .map(|i| Index {
+                        keys: i.keys.iter().map(ToString::to_string).collect(),
+                        columns: i.columns().into_iter().map(str::to_string).collect(),
+                        expressions: i
+                            .keys
+                            .iter()
+                            .filter_map(|key| match key {
+                                IndexKey::Expression(expression) => Some(expression.clone()),
+                                IndexKey::Column(_) => None,
+                            })
+                            .collect(),
+                        name: i.name,
+                        is_unique: i.is_unique,
+                        is_primary: i.is_primary,
+                        method: i.method,
+                        predicate: i.predicate,
                     })
                     .collect(),
             })
             .collect();
 
-        Database { tables }
-    }
-}
+        let enums: Vec<Enum> = db
+            .enums
+            .into_iter()
+            .map(|e| Enum {
+                schema: e.schema,
+                name: e.name,
+                labels: e.labels,
+            })
+            .collect();
 
-fn from_kind(kind: &str, is_required: bool) -> String {
-    let mut output = String::new();
+        let mut schemas: Vec<Schema> = Vec::new();
+        for table in &tables {
+            schema_mut(&mut schemas, &table.schema)
+                .tables
+                .push(table.clone());
+        }
+        for e in &enums {
+            schema_mut(&mut schemas, &e.schema).enums.push(e.clone());
+        }
 
-    if !is_required {
-        output.push_str("Option<");
+        Database {
+            tables,
+            enums,
+            schemas,
+        }
     }
+}
 
-    let kind = match kind {
-        "int4" => "i32".to_string(),
-        "int8" => "i64".to_string(),
-        "float4" => "f32".to_string(),
-        "float8" => "f64".to_string(),
-        "numeric" => "f64".to_string(),
-        "date" => "chrono::NaiveDate".to_string(),
-        "timestamp" | "timestamptz" | "timestamp with time zone" => {
-            "chrono::DateTime<chrono::FixedOffset>".to_string()
+fn schema_mut<'a>(schemas: &'a mut Vec<Schema>, name: &str) -> &'a mut Schema {
+    let index = match schemas.iter().position(|s| s.name == name) {
+        Some(index) => index,
+        None => {
+            schemas.push(Schema {
+                name: name.to_string(),
+                tables: Vec::new(),
+                enums: Vec::new(),
+            });
+            schemas.len() - 1
         }
-        "varchar" | "text" => "String".to_string(),
-        "bool" => "bool".to_string(),
-        "uuid" => "uuid::Uuid".to_string(),
-        _ => kind.to_string(),
     };
 
-    output.push_str(&kind);
-
-    if !is_required {
-        output.push('>');
-    }
-
-    output
+    &mut schemas[index]
 }
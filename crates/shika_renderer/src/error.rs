@@ -2,6 +2,10 @@
 pub enum Error {
     Render(tera::Error),
     FilterError(String),
+    IO(std::io::Error),
+    InvalidFrontMatter(String, serde_yml::Error),
+    MissingOutput(String),
+    DuplicateOutput(String, String),
 }
 
 impl From<tera::Error> for Error {
@@ -10,11 +14,30 @@ impl From<tera::Error> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::IO(value)
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::Render(error) => write!(f, "Renderer error: {error}"),
             Error::FilterError(_) => write!(f, "FilterError"),
+            Error::IO(error) => write!(f, "IO error: {error}"),
+            Error::InvalidFrontMatter(template, error) => {
+                write!(f, "Invalid front matter in {template}: {error}")
+            }
+            Error::MissingOutput(template) => write!(
+                f,
+                "No output path for {template}, in the config or its front matter"
+            ),
+            Error::DuplicateOutput(template, path) => write!(
+                f,
+                "{template} renders more than one file to {path}, so its output path needs to \
+                 tell them apart"
+            ),
         }
     }
 }
@@ -0,0 +1,139 @@
+use serde::Deserialize;
+use shika_workspace::RenderMode;
+
+use crate::Error;
+
+/// The keys of a front-matter block.
+const FIELDS: &[&str] = &["output", "mode", "skip_if"];
+
+/// The settings a template can declare in a YAML block at its top, delimited by `---` lines:
+///
+/// ```text
+/// ---
+/// output: src/models/{{ table.schema }}/{{ table.name | snake }}.rs
+/// mode: table
+/// skip_if: table.kind != "table"
+/// ---
+/// pub struct {{ table.name | pascal }} {
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrontMatter {
+    pub output: Option<String>,
+    pub mode: Option<RenderMode>,
+    pub skip_if: Option<String>,
+}
+
+impl FrontMatter {
+    /// Split a template into its front matter and the template itself. A template without a
+    /// front-matter block is returned unchanged, including one whose opening `---` is never
+    /// closed, and one that starts with a YAML document of its own, e.g. a Kubernetes
+    /// manifest.
+    ///
+    /// A leading YAML mapping is front matter when one of its keys is a front-matter key or
+    /// a misspelling of one, so a typo like `ouptut:` or `mode: tabel` is an error instead of
+    /// leaving the block in the template.
+    pub fn split<'a>(name: &str, source: &'a str) -> Result<(Self, &'a str), Error> {
+        let Some(rest) = source
+            .strip_prefix("---\n")
+            .or_else(|| source.strip_prefix("---\r\n"))
+        else {
+            return Ok((Self::default(), source));
+        };
+
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == "---" {
+                let front_matter = match rest[..offset].trim() {
+                    "" => Self::default(),
+                    yaml => {
+                        let Ok(serde_yml::Value::Mapping(mapping)) = serde_yml::from_str(yaml)
+                        else {
+                            return Ok((Self::default(), source));
+                        };
+                        let is_front_matter = mapping
+                            .keys()
+                            .filter_map(|key| key.as_str())
+                            .any(|key| FIELDS.iter().any(|field| distance(key, field) <= 2));
+                        if !is_front_matter {
+                            return Ok((Self::default(), source));
+                        }
+
+                        serde_yml::from_value(serde_yml::Value::Mapping(mapping))
+                            .map_err(|e| Error::InvalidFrontMatter(name.to_string(), e))?
+                    }
+                };
+
+                return Ok((front_matter, &rest[offset + line.len()..]));
+            }
+            offset += line.len();
+        }
+
+        Ok((Self::default(), source))
+    }
+}
+
+/// The Levenshtein distance between two keys, i.e. the number of characters to insert,
+/// remove or replace to turn one into the other.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(a != *b);
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_off_front_matter() {
+        let source = "---\noutput: out/{{ table.name }}.rs\nmode: table\n---\nstruct {}\n";
+        let (front_matter, template) = FrontMatter::split("model.tera", source).unwrap();
+
+        assert_eq!(
+            front_matter.output.as_deref(),
+            Some("out/{{ table.name }}.rs")
+        );
+        assert_eq!(front_matter.mode, Some(RenderMode::Table));
+        assert_eq!(template, "struct {}\n");
+    }
+
+    #[test]
+    fn leaves_templates_without_front_matter_alone() {
+        let source = "struct {}\n---\n";
+        let (front_matter, template) = FrontMatter::split("model.tera", source).unwrap();
+
+        assert!(front_matter.output.is_none());
+        assert_eq!(template, source);
+    }
+
+    #[test]
+    fn leaves_leading_yaml_that_is_not_front_matter_alone() {
+        let source = "---\napiVersion: v1\nkind: ConfigMap\n---\ndata: {}\n";
+        let (front_matter, template) = FrontMatter::split("config.tera", source).unwrap();
+
+        assert!(front_matter.output.is_none());
+        assert_eq!(template, source);
+    }
+
+    #[test]
+    fn rejects_typos() {
+        for source in [
+            "---\nouptut: out.rs\n---\n",
+            "---\noutput: out.rs\nmode: tabel\n---\n",
+        ] {
+            let error = FrontMatter::split("model.tera", source).unwrap_err();
+            assert!(matches!(error, Error::InvalidFrontMatter(..)), "{error}");
+        }
+    }
+}
@@ -1,28 +1,50 @@
 mod database;
 mod error;
+mod front_matter;
+mod types;
 
-use database::Database;
-use shika_workspace::Workspace;
+use std::{collections::HashMap, fs, path::Path};
+
+use database::{Database, Schema, Table};
+use front_matter::FrontMatter;
+use serde::Serialize;
+use shika_workspace::{RenderMode, Template, Workspace};
 use tera::{Context, Tera};
+use types::{Language, TypeMap};
 
 pub use error::Error;
 
 pub struct Renderer {
     engine: Tera,
+    /// The front matter of each template that has one, keyed by template path.
+    front_matter: HashMap<String, FrontMatter>,
+    /// The global `type_map` of the config keyed by language, which templates can extend.
+    type_map: HashMap<String, HashMap<String, String>>,
+    /// The global `overrides` of the config keyed by language, which templates can extend.
+    overrides: HashMap<String, HashMap<String, String>>,
 }
 
 impl Renderer {
+    /// Load every template in `.shika/templates`, named by its path relative to that
+    /// directory, with its front matter stripped.
     pub fn new(workspace: &Workspace) -> Result<Self, Error> {
-        let path = workspace
-            .path
-            .join(".shika")
-            .join("templates")
-            .join("**")
-            .join("*")
-            .display()
-            .to_string();
+        let mut sources = Vec::new();
+        read_templates(
+            &workspace.path.join(".shika").join("templates"),
+            "",
+            &mut sources,
+        )?;
+
+        let mut templates = Vec::new();
+        let mut front_matter = HashMap::new();
+        for (name, source) in &sources {
+            let (matter, template) = FrontMatter::split(name, source)?;
+            front_matter.insert(name.clone(), matter);
+            templates.push((name.as_str(), template));
+        }
 
-        let mut engine = Tera::new(path.as_ref())?;
+        let mut engine = Tera::default();
+        engine.add_raw_templates(templates)?;
 
         engine.register_filter("primary_keys", filters::primary_keys);
         engine.register_filter("foreign_keys", filters::foreign_keys);
@@ -33,20 +55,215 @@ impl Renderer {
         engine.register_filter("snake", filters::snake);
         engine.register_filter("camel", filters::camel);
 
-        Ok(Self { engine })
+        engine.register_filter("doc_comment", filters::DocComment { style: "///" });
+
+        Ok(Self {
+            engine,
+            front_matter,
+            type_map: workspace.config.type_map.clone(),
+            overrides: workspace.config.overrides.clone(),
+        })
+    }
+
+    /// Render a template over the snapshot, with column types in the template's language.
+    ///
+    /// Settings missing from the config are taken from the template's front matter. Returns
+    /// every generated file, i.e. one per database, schema or table that isn't skipped,
+    /// followed by the index if the template has one. Two files with the same path are an
+    /// error, e.g. for tables of the same name in different schemas when the output path
+    /// leaves out `table.schema`.
+    pub fn render(
+        &mut self,
+        template: &Template,
+        database: &shika_database::Database,
+    ) -> Result<Vec<Output>, Error> {
+        let front_matter = self.front_matter(&template.path);
+        let output = template
+            .output
+            .clone()
+            .or(front_matter.output)
+            .ok_or(Error::MissingOutput(template.path.clone()))?;
+        let mode = template.mode.or(front_matter.mode).unwrap_or_default();
+        let skip_if = template.skip_if.clone().or(front_matter.skip_if);
+
+        let language = Language::from_name(&template.language);
+        let mut types = for_language(&self.type_map, &template.language, language);
+        types.extend(template.type_map.clone());
+        let mut overrides = for_language(&self.overrides, &template.language, language);
+        overrides.extend(template.overrides.clone());
+
+        self.engine.register_filter(
+            "doc_comment",
+            filters::DocComment {
+                style: Language::comment_style(language),
+            },
+        );
+
+        let type_map = TypeMap::new(language, types, overrides);
+        let data = Database::new(database.clone(), &type_map);
+        let context = Context::from_serialize(&data)?;
+
+        let mut subjects = Vec::new();
+        match mode {
+            RenderMode::Database => subjects.push((context.clone(), None, None)),
+            RenderMode::Schema => {
+                for schema in &data.schemas {
+                    let mut context = context.clone();
+                    context.insert("schema", schema);
+                    subjects.push((context, None, Some(schema)));
+                }
+            }
+            RenderMode::Table => {
+                for table in &data.tables {
+                    let mut context = context.clone();
+                    context.insert("table", table);
+                    subjects.push((context, Some(table), None));
+                }
+            }
+        }
+
+        let mut outputs = Vec::new();
+        let mut files = Vec::new();
+        for (context, table, schema) in subjects {
+            let is_skipped = match &skip_if {
+                Some(condition) => self.is_true(condition, &context)?,
+                None => false,
+            };
+            if is_skipped {
+                continue;
+            }
+
+            let output = self.render_file(&template.path, &output, &context)?;
+            if outputs.iter().any(|o: &Output| o.path == output.path) {
+                return Err(Error::DuplicateOutput(template.path.clone(), output.path));
+            }
+            files.push(GeneratedFile::new(&output.path, table, schema));
+            outputs.push(output);
+        }
+
+        if let Some(index) = &template.index {
+            let output = index
+                .output
+                .clone()
+                .or(self.front_matter(&index.path).output)
+                .ok_or(Error::MissingOutput(index.path.clone()))?;
+
+            let mut context = context;
+            context.insert("files", &files);
+
+            outputs.push(self.render_file(&index.path, &output, &context)?);
+        }
+
+        Ok(outputs)
+    }
+
+    fn front_matter(&self, path: &str) -> FrontMatter {
+        self.front_matter.get(path).cloned().unwrap_or_default()
+    }
+
+    /// Evaluate a Tera expression, e.g. `table.kind == "view"`.
+    fn is_true(&mut self, expression: &str, context: &Context) -> Result<bool, Error> {
+        let template = format!("{{% if {expression} %}}true{{% endif %}}");
+
+        Ok(self.engine.render_str(&template, context)? == "true")
+    }
+
+    fn render_file(
+        &mut self,
+        path: &str,
+        output: &str,
+        context: &Context,
+    ) -> Result<Output, Error> {
+        Ok(Output {
+            path: self.engine.render_str(output, context)?.trim().to_string(),
+            content: self.engine.render(path, context)?,
+        })
+    }
+}
+
+/// A rendered file, with its path relative to the workspace root.
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub path: String,
+    pub content: String,
+}
+
+/// A file generated by a template, as the index template sees it.
+#[derive(Serialize)]
+struct GeneratedFile<'a> {
+    path: String,
+    /// The file name without its extension, e.g. `users` for `src/models/users.rs`.
+    name: String,
+    table: Option<&'a Table>,
+    schema: Option<&'a Schema>,
+}
+
+impl<'a> GeneratedFile<'a> {
+    fn new(path: &str, table: Option<&'a Table>, schema: Option<&'a Schema>) -> Self {
+        let name = Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Self {
+            path: path.to_string(),
+            name,
+            table,
+            schema,
+        }
     }
+}
+
+/// The global types of a template's language, under its name or any other name of the same
+/// language, e.g. `rs` for `rust`.
+fn for_language(
+    types: &HashMap<String, HashMap<String, String>>,
+    name: &str,
+    language: Option<Language>,
+) -> HashMap<String, String> {
+    types
+        .iter()
+        .filter(|(key, _)| {
+            key.eq_ignore_ascii_case(name)
+                || language.is_some() && Language::from_name(key) == language
+        })
+        .flat_map(|(_, types)| types.clone())
+        .collect()
+}
 
-    pub fn render(&self, template: &str, data: &Database) -> Result<String, Error> {
-        let context = Context::from_serialize(data)?;
-        Ok(self.engine.render(template, &context)?)
+/// Read the templates in a directory and its subdirectories as `(name, source)` pairs, where
+/// the name is the path relative to the templates directory, e.g. `models/user.tera`.
+fn read_templates(
+    directory: &Path,
+    prefix: &str,
+    templates: &mut Vec<(String, String)>,
+) -> Result<(), Error> {
+    if !directory.is_dir() {
+        return Ok(());
     }
+
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        let name = format!(
+            "{prefix}{}",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        );
+
+        if path.is_dir() {
+            read_templates(&path, &format!("{name}/"), templates)?;
+        } else {
+            templates.push((name, fs::read_to_string(&path)?));
+        }
+    }
+
+    Ok(())
 }
 
 mod filters {
     use std::collections::HashMap;
 
     use convert_case::{Case, Casing};
-    use tera::{Error, Result, Value};
+    use tera::{Error, Filter, Result, Value};
 
     pub fn primary_keys(value: &Value, _args: &HashMap<String, Value>) -> Result<Value> {
         Ok(value
@@ -139,4 +356,104 @@ mod filters {
 
         Ok(Value::String(text))
     }
+
+    /// Turn a (possibly multi-line) comment into a comment block, e.g.
+    /// `{{ column.comment | doc_comment(style="/**") }}`.
+    ///
+    /// Supported styles are `///`, `/**`, `//` and `#`, defaulting to the one of the
+    /// template's language. A null comment renders as an empty string, so the filter can be
+    /// applied to columns without a comment.
+    pub struct DocComment {
+        /// The style used when the template doesn't pass one.
+        pub style: &'static str,
+    }
+
+    impl Filter for DocComment {
+        fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+            if value.is_null() {
+                return Ok(Value::String(String::new()));
+            }
+
+            let text = value.as_str().ok_or(Error::msg(Value::String(
+                "Value is not a string".to_string(),
+            )))?;
+
+            if text.trim().is_empty() {
+                return Ok(Value::String(String::new()));
+            }
+
+            let style = match args.get("style") {
+                Some(style) => style.as_str().ok_or(Error::msg(Value::String(
+                    "Style is not a string".to_string(),
+                )))?,
+                None => self.style,
+            };
+
+            let prefix = match style {
+                "///" => "///",
+                "/**" => " *",
+                "//" => "//",
+                "#" => "#",
+                _ => return Err(Error::msg(format!("Unknown comment style: {style}"))),
+            };
+
+            let mut lines: Vec<String> = text
+                .trim()
+                .lines()
+                .map(|line| match line.trim_end() {
+                    "" => prefix.to_string(),
+                    // A `*/` in the text would end a block comment early.
+                    line if style == "/**" => format!("{prefix} {}", line.replace("*/", "*\\/")),
+                    line => format!("{prefix} {line}"),
+                })
+                .collect();
+
+            if style == "/**" {
+                lines.insert(0, "/**".to_string());
+                lines.push(" */".to_string());
+            }
+
+            Ok(Value::String(lines.join("\n")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_the_types_of_every_name_of_a_language() {
+        let types = HashMap::from([
+            (
+                "rust".to_string(),
+                HashMap::from([("uuid".to_string(), "Uuid".to_string())]),
+            ),
+            (
+                "RS".to_string(),
+                HashMap::from([("json".to_string(), "Value".to_string())]),
+            ),
+            (
+                "go".to_string(),
+                HashMap::from([("uuid".to_string(), "string".to_string())]),
+            ),
+            (
+                "sql".to_string(),
+                HashMap::from([("int4".to_string(), "int".to_string())]),
+            ),
+        ]);
+
+        let rust = for_language(&types, "rust", Some(Language::Rust));
+        assert_eq!(rust.len(), 2);
+        assert_eq!(rust["uuid"], "Uuid");
+        assert_eq!(rust["json"], "Value");
+
+        // Languages without built-in types only match by name.
+        let sql = for_language(&types, "SQL", None);
+        assert_eq!(
+            sql,
+            HashMap::from([("int4".to_string(), "int".to_string())])
+        );
+        assert!(for_language(&types, "elixir", None).is_empty());
+    }
 }
@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+
+use convert_case::{Case, Casing};
+use shika_database::{Column, DataType};
+
+/// The languages with a built-in type map, chosen by `Template::language`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    TypeScript,
+    Go,
+    Python,
+    Kotlin,
+}
+
+impl Language {
+    /// The language with a built-in type map by that name, or `None` for any other language,
+    /// e.g. `markdown` or `sql`, whose templates get the SQL types as they are.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "rust" | "rs" => Some(Language::Rust),
+            "typescript" | "ts" => Some(Language::TypeScript),
+            "go" | "golang" => Some(Language::Go),
+            "python" | "py" => Some(Language::Python),
+            "kotlin" | "kt" => Some(Language::Kotlin),
+            _ => None,
+        }
+    }
+
+    /// The style of doc comments in a language, as the `doc_comment` filter takes it, which is
+    /// `///` for languages without a built-in type map.
+    pub fn comment_style(language: Option<Self>) -> &'static str {
+        match language {
+            Some(Language::Rust) | None => "///",
+            Some(Language::TypeScript | Language::Kotlin) => "/**",
+            Some(Language::Go) => "//",
+            Some(Language::Python) => "#",
+        }
+    }
+}
+
+/// Resolves SQL column types to the types of a target language.
+///
+/// Configured types take precedence over the built-in map of the language, and overrides for
+/// a specific column take precedence over both. Without a language, columns keep their SQL
+/// types, e.g. `varchar(64)` or `int4[]`.
+#[derive(Debug, Clone)]
+pub struct TypeMap {
+    language: Option<Language>,
+    /// Target types keyed by SQL type, either the bare name (`numeric`) or with its modifiers
+    /// (`numeric(10,2)`).
+    types: HashMap<String, String>,
+    /// Target types keyed by `table.column` or `schema.table.column`.
+    overrides: HashMap<String, String>,
+}
+
+impl TypeMap {
+    pub fn new(
+        language: Option<Language>,
+        types: HashMap<String, String>,
+        overrides: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            language,
+            types,
+            overrides,
+        }
+    }
+
+    /// The type of a column in the target language, including arrays and nullability, e.g.
+    /// `Option<Vec<i32>>` in Rust or `number[] | null` in TypeScript.
+    pub fn kind(&self, schema: &str, table: &str, column: &Column) -> String {
+        let base_type = self.base_type(schema, table, column);
+
+        match column.required {
+            true => base_type,
+            false => self.nullable(&base_type),
+        }
+    }
+
+    /// The type of a column in the target language without nullability, e.g. `Vec<i32>` in
+    /// Rust. An override replaces the type including arrays.
+    pub fn base_type(&self, schema: &str, table: &str, column: &Column) -> String {
+        let overridden = self
+            .overrides
+            .get(&format!("{schema}.{table}.{}", column.name))
+            .or_else(|| self.overrides.get(&format!("{table}.{}", column.name)));
+        if let Some(base_type) = overridden {
+            return base_type.clone();
+        }
+
+        let mut base_type = self.element_type(column);
+        for _ in 0..column.data_type.dimensions {
+            base_type = self.array(&base_type);
+        }
+
+        base_type
+    }
+
+    /// The type of a single value of the column, without arrays or nullability.
+    fn element_type(&self, column: &Column) -> String {
+        // Snapshots pulled before types were structured only carry the plain `kind`.
+        let name = match column.data_type.name.is_empty() {
+            true => column.kind.as_str(),
+            false => column.data_type.name.as_str(),
+        };
+
+        let sql_type = column.sql_type();
+        let configured = self
+            .types
+            .get(sql_type.trim_end_matches("[]"))
+            .or_else(|| self.types.get(name));
+        if let Some(kind) = configured {
+            return kind.clone();
+        }
+
+        let Some(language) = self.language else {
+            return sql_type.trim_end_matches("[]").to_string();
+        };
+
+        if let Some(enum_name) = &column.enum_name {
+            return enum_name.to_case(Case::Pascal);
+        }
+
+        let postgres_name = postgres_name(name, &column.data_type);
+        let builtin = match language {
+            Language::Rust => rust(postgres_name, &column.data_type),
+            Language::TypeScript => typescript(postgres_name),
+            Language::Go => go(postgres_name, &column.data_type),
+            Language::Python => python(postgres_name),
+            Language::Kotlin => kotlin(postgres_name, &column.data_type),
+        };
+
+        builtin.unwrap_or(name).to_string()
+    }
+
+    fn array(&self, kind: &str) -> String {
+        match self.language {
+            Some(Language::Rust) => format!("Vec<{kind}>"),
+            Some(Language::TypeScript) | None => format!("{kind}[]"),
+            Some(Language::Go) => format!("[]{kind}"),
+            Some(Language::Python) => format!("list[{kind}]"),
+            Some(Language::Kotlin) => format!("List<{kind}>"),
+        }
+    }
+
+    /// SQL types have no nullable form, so without a language the type stays as it is.
+    fn nullable(&self, kind: &str) -> String {
+        match self.language {
+            Some(Language::Rust) => format!("Option<{kind}>"),
+            Some(Language::TypeScript) => format!("{kind} | null"),
+            Some(Language::Go) => format!("*{kind}"),
+            Some(Language::Python) => format!("{kind} | None"),
+            Some(Language::Kotlin) => format!("{kind}?"),
+            None => kind.to_string(),
+        }
+    }
+}
+
+/// The PostgreSQL name of a MySQL or SQLite type, which the built-in maps are keyed by, e.g.
+/// `int8` for `bigint` or `timestamp` for `datetime`. PostgreSQL names are returned as they are.
+fn postgres_name<'a>(name: &'a str, data_type: &DataType) -> &'a str {
+    match name {
+        "tinyint" if data_type.length == Some(1) || data_type.udt_name == "tinyint(1)" => "bool",
+        "tinyint" | "smallint" | "year" => "int2",
+        "int" | "integer" | "mediumint" => "int4",
+        "bigint" => "int8",
+        "float" => "float4",
+        "real" | "double" | "double precision" => "float8",
+        "decimal" => "numeric",
+        "boolean" => "bool",
+        "datetime" => "timestamp",
+        "char" | "character" | "nchar" => "bpchar",
+        "character varying" | "nvarchar" => "varchar",
+        "tinytext" | "mediumtext" | "longtext" | "clob" => "text",
+        "blob" | "tinyblob" | "mediumblob" | "longblob" | "binary" | "varbinary" => "bytea",
+        name => name,
+    }
+}
+
+fn rust(name: &str, data_type: &DataType) -> Option<&'static str> {
+    Some(match name {
+        "int2" => "i16",
+        "int4" => "i32",
+        "int8" => "i64",
+        "float4" => "f32",
+        "float8" => "f64",
+        "numeric" if data_type.scale.is_some() => "rust_decimal::Decimal",
+        "numeric" => "f64",
+        "date" => "chrono::NaiveDate",
+        "time" => "chrono::NaiveTime",
+        "timestamp" | "timestamptz" | "timestamp with time zone" => {
+            "chrono::DateTime<chrono::FixedOffset>"
+        }
+        "varchar" | "bpchar" | "text" => "String",
+        "bool" => "bool",
+        "bytea" => "Vec<u8>",
+        "uuid" => "uuid::Uuid",
+        _ => return None,
+    })
+}
+
+/// The types node-postgres returns, which gives 64-bit integers and numerics as strings to
+/// keep their precision.
+fn typescript(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "int2" | "int4" | "float4" | "float8" => "number",
+        "int8" | "numeric" => "string",
+        "date" | "timestamp" | "timestamptz" | "timestamp with time zone" => "Date",
+        "time" | "varchar" | "bpchar" | "text" | "uuid" => "string",
+        "bool" => "boolean",
+        "bytea" => "Buffer",
+        "json" | "jsonb" => "unknown",
+        _ => return None,
+    })
+}
+
+fn go(name: &str, data_type: &DataType) -> Option<&'static str> {
+    Some(match name {
+        "int2" => "int16",
+        "int4" => "int32",
+        "int8" => "int64",
+        "float4" => "float32",
+        "float8" => "float64",
+        "numeric" if data_type.scale.is_some() => "decimal.Decimal",
+        "numeric" => "float64",
+        "date" | "time" | "timestamp" | "timestamptz" | "timestamp with time zone" => "time.Time",
+        "varchar" | "bpchar" | "text" => "string",
+        "bool" => "bool",
+        "bytea" => "[]byte",
+        "uuid" => "uuid.UUID",
+        "json" | "jsonb" => "json.RawMessage",
+        _ => return None,
+    })
+}
+
+fn python(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "int2" | "int4" | "int8" => "int",
+        "float4" | "float8" => "float",
+        "numeric" => "decimal.Decimal",
+        "date" => "datetime.date",
+        "time" => "datetime.time",
+        "timestamp" | "timestamptz" | "timestamp with time zone" => "datetime.datetime",
+        "varchar" | "bpchar" | "text" => "str",
+        "bool" => "bool",
+        "bytea" => "bytes",
+        "uuid" => "uuid.UUID",
+        "json" | "jsonb" => "typing.Any",
+        _ => return None,
+    })
+}
+
+fn kotlin(name: &str, data_type: &DataType) -> Option<&'static str> {
+    Some(match name {
+        "int2" => "Short",
+        "int4" => "Int",
+        "int8" => "Long",
+        "float4" => "Float",
+        "float8" => "Double",
+        "numeric" if data_type.scale.is_some() => "java.math.BigDecimal",
+        "numeric" => "Double",
+        "date" => "java.time.LocalDate",
+        "time" => "java.time.LocalTime",
+        "timestamp" => "java.time.LocalDateTime",
+        "timestamptz" | "timestamp with time zone" => "java.time.OffsetDateTime",
+        "varchar" | "bpchar" | "text" | "json" | "jsonb" => "String",
+        "bool" => "Boolean",
+        "bytea" => "ByteArray",
+        "uuid" => "java.util.UUID",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(declared_type: &str, required: bool) -> Column {
+        let (name, length) = match declared_type.split_once('(') {
+            Some((name, length)) => (name, length.trim_end_matches(')').parse().ok()),
+            None => (declared_type, None),
+        };
+
+        Column {
+            name: "value".to_string(),
+            kind: name.to_string(),
+            required,
+            referenced_by: Vec::new(),
+            references: None,
+            is_primary_key: false,
+            is_unique: false,
+            default: None,
+            is_identity: false,
+            identity_generation: None,
+            is_generated: false,
+            generation_expression: None,
+            enum_name: None,
+            enum_schema: None,
+            comment: None,
+            data_type: DataType {
+                name: name.to_string(),
+                udt_name: declared_type.to_string(),
+                length,
+                ..DataType::default()
+            },
+        }
+    }
+
+    fn kind(type_map: &TypeMap, column: &Column) -> String {
+        type_map.kind("main", "members", column)
+    }
+
+    #[test]
+    fn prefers_configured_types_to_built_in_ones() {
+        let types = HashMap::from([
+            ("int4".to_string(), "u32".to_string()),
+            ("varchar(64)".to_string(), "Name".to_string()),
+        ]);
+        let rust = TypeMap::new(Some(Language::Rust), types, HashMap::new());
+
+        assert_eq!(kind(&rust, &column("int4", true)), "u32");
+        assert_eq!(kind(&rust, &column("int4", false)), "Option<u32>");
+        assert_eq!(kind(&rust, &column("varchar(64)", true)), "Name");
+        assert_eq!(kind(&rust, &column("varchar(32)", true)), "String");
+    }
+
+    #[test]
+    fn prefers_overrides_to_configured_types() {
+        let types = HashMap::from([("int4".to_string(), "u32".to_string())]);
+        let overrides = HashMap::from([
+            ("members.value".to_string(), "MemberId".to_string()),
+            ("main.members.value".to_string(), "MainMemberId".to_string()),
+        ]);
+        let rust = TypeMap::new(Some(Language::Rust), types, overrides);
+        assert_eq!(kind(&rust, &column("int4", true)), "MainMemberId");
+        assert_eq!(
+            rust.kind("other", "members", &column("int4", false)),
+            "Option<MemberId>"
+        );
+        assert_eq!(rust.kind("main", "teams", &column("int4", true)), "u32");
+    }
+
+    #[test]
+    fn maps_sqlite_types() {
+        let rust = TypeMap::new(Some(Language::Rust), HashMap::new(), HashMap::new());
+        assert_eq!(kind(&rust, &column("integer", true)), "i32");
+        assert_eq!(kind(&rust, &column("text", false)), "Option<String>");
+        assert_eq!(kind(&rust, &column("real", true)), "f64");
+        assert_eq!(kind(&rust, &column("blob", true)), "Vec<u8>");
+
+        let mut balance = column("decimal", true);
+        balance.data_type.precision = Some(10);
+        balance.data_type.scale = Some(2);
+        assert_eq!(kind(&rust, &balance), "rust_decimal::Decimal");
+
+        let typescript = TypeMap::new(Some(Language::TypeScript), HashMap::new(), HashMap::new());
+        assert_eq!(kind(&typescript, &column("integer", true)), "number");
+        assert_eq!(kind(&typescript, &column("varchar(64)", true)), "string");
+    }
+
+    #[test]
+    fn maps_mysql_types() {
+        let rust = TypeMap::new(Some(Language::Rust), HashMap::new(), HashMap::new());
+        assert_eq!(kind(&rust, &column("int", true)), "i32");
+        assert_eq!(kind(&rust, &column("bigint", true)), "i64");
+        assert_eq!(kind(&rust, &column("tinyint(1)", true)), "bool");
+        assert_eq!(kind(&rust, &column("tinyint", true)), "i16");
+        assert_eq!(kind(&rust, &column("double", true)), "f64");
+        assert_eq!(
+            kind(&rust, &column("datetime", true)),
+            "chrono::DateTime<chrono::FixedOffset>"
+        );
+
+        let kotlin = TypeMap::new(Some(Language::Kotlin), HashMap::new(), HashMap::new());
+        assert_eq!(kind(&kotlin, &column("longtext", false)), "String?");
+    }
+
+    #[test]
+    fn passes_types_through_without_a_language() {
+        let none = TypeMap::new(None, HashMap::new(), HashMap::new());
+        assert_eq!(kind(&none, &column("integer", false)), "integer");
+    }
+}
@@ -1,20 +1,153 @@
 use std::{collections::HashMap, fs::File, path::Path};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, de::Error as _};
+use shika_database::{Change, Severity, Source};
 
 use crate::Error;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
+    /// The introspection backend. Without it, the backend is chosen from the URL scheme.
+    #[serde(default)]
+    pub source: Option<Source>,
+    pub schemas: Option<Vec<String>>,
     pub exclude_tables: Option<Vec<String>>,
     pub templates: HashMap<String, Template>,
+    /// Target types keyed by language and then by SQL type, e.g.
+    /// `rust: { jsonb: serde_json::Value }`, for every template in that language.
+    #[serde(default)]
+    pub type_map: HashMap<String, HashMap<String, String>>,
+    /// Target types keyed by language and then by `table.column` or `schema.table.column`, for
+    /// every template in that language.
+    #[serde(default)]
+    pub overrides: HashMap<String, HashMap<String, String>>,
+    #[serde(default)]
+    pub migrations: Option<Migrations>,
+    #[serde(default)]
+    pub compatibility: Compatibility,
 }
 
+/// A template in `.shika/templates`. The output path, mode and `skip_if` condition can also be
+/// declared in a YAML front-matter block at the top of the template file, which the config
+/// takes precedence over.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Template {
     pub path: String,
-    pub output: String,
+    /// The output path, relative to the workspace root. It is a Tera expression rendered with
+    /// the same context as the template, e.g.
+    /// `src/models/{{ table.schema }}/{{ table.name | snake }}.rs`. Every file it renders needs
+    /// a path of its own.
+    #[serde(default)]
+    pub output: Option<String>,
     pub language: String,
+    #[serde(default)]
+    pub mode: Option<RenderMode>,
+    /// A Tera expression that skips a file when it is true, e.g. `table.kind == "view"`.
+    #[serde(default)]
+    pub skip_if: Option<String>,
+    /// A template rendered once over all files generated by this one, e.g. a `mod.rs`.
+    #[serde(default)]
+    pub index: Option<IndexTemplate>,
+    /// Target types keyed by SQL type, taking precedence over the global `type_map`.
+    #[serde(default)]
+    pub type_map: HashMap<String, String>,
+    /// Target types keyed by column, taking precedence over the global `overrides`.
+    #[serde(default)]
+    pub overrides: HashMap<String, String>,
+}
+
+/// What a template is rendered for.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderMode {
+    /// Once, into a single file.
+    #[default]
+    Database,
+    /// Once per schema, with the schema as `schema` in the context.
+    Schema,
+    /// Once per table, with the table as `table` in the context.
+    Table,
+}
+
+/// A template rendered over the files generated by another one, which are available as
+/// `files` in the context, each with its `path`, its file `name`, and its `table` or `schema`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexTemplate {
+    pub path: String,
+    /// The output path, which is a Tera expression like `Template::output`. It can also be
+    /// declared in the front matter of the index template.
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+/// Where `shika migration` writes its SQL files, and how they are named.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Migrations {
+    /// The migrations directory, relative to the workspace root.
+    pub path: String,
+    #[serde(default)]
+    pub format: MigrationFormat,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationFormat {
+    /// `<timestamp>_<name>.up.sql` and `<timestamp>_<name>.down.sql`.
+    #[default]
+    Sqlx,
+    /// `V<version>__<name>.sql`. Refinery has no down migrations, so none is written.
+    Refinery,
+}
+
+/// The policy of `shika check-compat`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Compatibility {
+    /// The lowest severity that fails the check.
+    pub fail_on: Severity,
+    /// Severities replacing the defaults, keyed by the kind of change, e.g.
+    /// `column_type_changed: breaking`. A key that isn't the name of a kind of change is an
+    /// error, so that a typo doesn't silently keep the default.
+    #[serde(deserialize_with = "deserialize_severities")]
+    pub severities: HashMap<String, Severity>,
+}
+
+impl Default for Compatibility {
+    fn default() -> Self {
+        Self {
+            fail_on: Severity::Breaking,
+            severities: HashMap::new(),
+        }
+    }
+}
+
+impl Compatibility {
+    /// The severity of a change under this policy.
+    pub fn severity(&self, change: &Change) -> Severity {
+        self.severities
+            .get(change.name())
+            .copied()
+            .unwrap_or(change.severity())
+    }
+
+    /// Whether a change of this severity fails the check.
+    pub fn fails(&self, severity: Severity) -> bool {
+        severity >= self.fail_on
+    }
+}
+
+fn deserialize_severities<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, Severity>, D::Error> {
+    let severities = HashMap::<String, Severity>::deserialize(deserializer)?;
+    if let Some(name) = severities
+        .keys()
+        .find(|name| !Change::NAMES.contains(&name.as_str()))
+    {
+        return Err(D::Error::unknown_variant(name, Change::NAMES));
+    }
+
+    Ok(severities)
 }
 
 impl Config {
@@ -25,3 +158,57 @@ impl Config {
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_change(from: &str, to: &str) -> Change {
+        Change::ColumnTypeChanged {
+            schema: "public".to_string(),
+            table: "users".to_string(),
+            column: "id".to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn replaces_default_severities() {
+        let policy: Compatibility =
+            serde_yml::from_str("severities: { column_type_changed: safe }").unwrap();
+
+        assert_eq!(policy.fail_on, Severity::Breaking);
+        assert_eq!(
+            policy.severity(&type_change("int8", "int4")),
+            Severity::Safe
+        );
+        assert_eq!(
+            Compatibility::default().severity(&type_change("int8", "int4")),
+            Severity::Breaking
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_changes() {
+        let error =
+            serde_yml::from_str::<Compatibility>("severities: { column_type_change: safe }")
+                .unwrap_err();
+
+        assert!(
+            error
+                .to_string()
+                .contains("unknown variant `column_type_change`")
+        );
+    }
+
+    #[test]
+    fn fails_on_the_configured_severity_and_above() {
+        let policy: Compatibility = serde_yml::from_str("fail_on: risky").unwrap();
+
+        assert!(!policy.fails(Severity::Safe));
+        assert!(policy.fails(Severity::Risky));
+        assert!(policy.fails(Severity::Breaking));
+        assert!(!Compatibility::default().fails(Severity::Risky));
+    }
+}
@@ -2,6 +2,6 @@ mod config;
 mod error;
 mod workspace;
 
-pub use config::Template;
+pub use config::{Compatibility, IndexTemplate, MigrationFormat, Migrations, RenderMode, Template};
 pub use error::Error;
 pub use workspace::Workspace;